@@ -0,0 +1,91 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::f64::consts::PI;
+
+// Fixed seed so every run benchmarks the same bodies
+const SEED: u64 = 42;
+const BOX_SIZE: f64 = 100.0;
+const NUM_CLUSTERS: usize = 8;
+const CLUSTER_RADIUS: f64 = 2.0;
+// Separation of the bodies in a coincident pair. Exactly coincident bodies
+// would never be separated by the tree, so they are a tiny distance apart.
+const PAIR_SEPARATION: f64 = 1e-9;
+
+// The initial distributions the benchmarks are run over
+#[derive(Clone, Copy)]
+pub enum Distribution {
+    Uniform,
+    Clustered,
+    Disk,
+    CoincidentPairs,
+}
+pub const DISTRIBUTIONS: [Distribution; 4] = [
+    Distribution::Uniform,
+    Distribution::Clustered,
+    Distribution::Disk,
+    Distribution::CoincidentPairs,
+];
+pub const NUM_BODIES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+
+impl Distribution {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Distribution::Uniform => "uniform",
+            Distribution::Clustered => "clustered",
+            Distribution::Disk => "disk",
+            Distribution::CoincidentPairs => "coincident_pairs",
+        }
+    }
+    // Generates the x and y positions of num_bodies bodies
+    pub fn generate(&self, num_bodies: usize) -> (Vec<f64>, Vec<f64>) {
+        let mut rng: StdRng = StdRng::seed_from_u64(SEED);
+        let mut x: Vec<f64> = Vec::with_capacity(num_bodies);
+        let mut y: Vec<f64> = Vec::with_capacity(num_bodies);
+        match self {
+            Distribution::Uniform => {
+                for _ in 0..num_bodies {
+                    x.push(rng.gen_range(-BOX_SIZE / 2.0..BOX_SIZE / 2.0));
+                    y.push(rng.gen_range(-BOX_SIZE / 2.0..BOX_SIZE / 2.0));
+                }
+            }
+            Distribution::Clustered => {
+                let centres: Vec<(f64, f64)> = (0..NUM_CLUSTERS)
+                    .map(|_| {
+                        (
+                            rng.gen_range(-BOX_SIZE / 2.0..BOX_SIZE / 2.0),
+                            rng.gen_range(-BOX_SIZE / 2.0..BOX_SIZE / 2.0),
+                        )
+                    })
+                    .collect();
+                for i in 0..num_bodies {
+                    // Approximately gaussian offsets from the sum of uniform samples
+                    let (centre_x, centre_y) = centres[i % NUM_CLUSTERS];
+                    let offset_x: f64 = (0..4).map(|_| rng.gen::<f64>() - 0.5).sum::<f64>();
+                    let offset_y: f64 = (0..4).map(|_| rng.gen::<f64>() - 0.5).sum::<f64>();
+                    x.push(centre_x + offset_x * CLUSTER_RADIUS);
+                    y.push(centre_y + offset_y * CLUSTER_RADIUS);
+                }
+            }
+            Distribution::Disk => {
+                for _ in 0..num_bodies {
+                    // Uniform in area
+                    let r: f64 = BOX_SIZE / 2.0 * rng.gen::<f64>().sqrt();
+                    let angle: f64 = rng.gen_range(0.0..2.0 * PI);
+                    x.push(r * angle.cos());
+                    y.push(r * angle.sin());
+                }
+            }
+            Distribution::CoincidentPairs => {
+                for i in 0..num_bodies {
+                    if i % 2 == 0 {
+                        x.push(rng.gen_range(-BOX_SIZE / 2.0..BOX_SIZE / 2.0));
+                        y.push(rng.gen_range(-BOX_SIZE / 2.0..BOX_SIZE / 2.0));
+                    } else {
+                        x.push(x[i - 1] + PAIR_SEPARATION);
+                        y.push(y[i - 1] + PAIR_SEPARATION);
+                    }
+                }
+            }
+        }
+        (x, y)
+    }
+}
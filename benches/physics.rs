@@ -0,0 +1,117 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use space_clicker::simulation::bh_tree::Tree;
+use space_clicker::simulation::calc_acceleration::add_node_acceleration;
+use space_clicker::simulation::Simulation;
+
+mod distributions;
+
+use distributions::{DISTRIBUTIONS, NUM_BODIES};
+
+const SAMPLE_SIZE: usize = 10;
+const DT: f64 = 0.1;
+const GRAVITY: f64 = 1.0;
+
+// Sets the root of the tree the same way the web build does when drawing:
+// centred on the centre of mass and twice as wide as the farthest body
+fn fit_root(tree: &mut Tree, x: &[f64], y: &[f64]) {
+    let num_bodies: usize = x.len();
+    let com_x: f64 = x.iter().sum::<f64>() / num_bodies as f64;
+    let com_y: f64 = y.iter().sum::<f64>() / num_bodies as f64;
+    let mut max_distance: f64 = 0.0;
+    for i in 0..num_bodies {
+        let dx: f64 = x[i] - com_x;
+        let dy: f64 = y[i] - com_y;
+        max_distance = max_distance.max((dx * dx + dy * dy).sqrt());
+    }
+    tree.root_centre = (com_x, com_y);
+    tree.root_half_width = max_distance * 2.0;
+}
+
+fn bench_construct(c: &mut Criterion) {
+    let mut group = c.benchmark_group("web/tree_construct");
+    group.sample_size(SAMPLE_SIZE);
+    for distribution in DISTRIBUTIONS {
+        for num_bodies in NUM_BODIES {
+            let (x, y) = distribution.generate(num_bodies);
+            let masses: Vec<f64> = vec![1.0; num_bodies];
+            let mut tree: Tree = Tree::new();
+            fit_root(&mut tree, &x, &y);
+            group.bench_with_input(
+                BenchmarkId::new(distribution.name(), num_bodies),
+                &num_bodies,
+                |b, &num_bodies| b.iter(|| tree.construct(&x, &y, &masses, num_bodies)),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_acceleration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("web/add_node_acceleration");
+    group.sample_size(SAMPLE_SIZE);
+    for distribution in DISTRIBUTIONS {
+        for num_bodies in NUM_BODIES {
+            let (x, y) = distribution.generate(num_bodies);
+            let masses: Vec<f64> = vec![1.0; num_bodies];
+            let mut tree: Tree = Tree::new();
+            fit_root(&mut tree, &x, &y);
+            tree.construct(&x, &y, &masses, num_bodies);
+            group.bench_with_input(
+                BenchmarkId::new(distribution.name(), num_bodies),
+                &num_bodies,
+                |b, &num_bodies| {
+                    b.iter(|| {
+                        for i in 0..num_bodies {
+                            black_box(add_node_acceleration(
+                                (&x[i], &y[i]),
+                                &tree,
+                                0,
+                                tree.root_half_width,
+                            ));
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+// A simulation of the bodies at rest, ready to step
+fn simulation(x: &[f64], y: &[f64]) -> Simulation {
+    let mut sim: Simulation = Simulation::new_empty();
+    for (&x, &y) in x.iter().zip(y) {
+        sim.add_body(x, y, 0.0, 0.0);
+    }
+    sim.dt = DT;
+    sim.gravity = GRAVITY;
+    sim.fit_tree_root();
+    sim
+}
+
+fn bench_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("web/step");
+    group.sample_size(SAMPLE_SIZE);
+    for distribution in DISTRIBUTIONS {
+        for num_bodies in NUM_BODIES {
+            let (x, y) = distribution.generate(num_bodies);
+            // Every sample steps a fresh copy of the initial state
+            group.bench_with_input(
+                BenchmarkId::new(distribution.name(), num_bodies),
+                &num_bodies,
+                |b, _| {
+                    b.iter_batched(
+                        || simulation(&x, &y),
+                        |mut sim| sim.update(),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_construct, bench_acceleration, bench_step);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+// Generates the C header for the API in src/ffi.rs
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir: String = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config: cbindgen::Config =
+        cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap();
+    match cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+    {
+        Ok(bindings) => {
+            // Only write the header when it changes, so builds leave include/ alone
+            let path: String = format!("{}/include/space_clicker.h", crate_dir);
+            let mut header: Vec<u8> = Vec::new();
+            bindings.write(&mut header);
+            if std::fs::read(&path).ok().as_ref() != Some(&header) {
+                if let Err(err) = std::fs::write(&path, &header) {
+                    panic!("Error writing '{}': {}", path, err);
+                }
+            }
+        }
+        Err(err) => panic!("Error generating C header: {}", err),
+    }
+}
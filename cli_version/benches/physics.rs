@@ -0,0 +1,136 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use space_clicker::input::Constants;
+use space_clicker::physics::bh_tree::{construct_tree, Tree, ROOT_NODE_INDEX};
+use space_clicker::physics::logger::compressed::PositionLogFormat;
+use space_clicker::physics::logger::npy::NpyOutput;
+use space_clicker::physics::removal::RemovalPolicy;
+use space_clicker::physics::{add_node_acceleration, step, Vec2D};
+use space_clicker::timing::Timer;
+
+// Shared with the web build's benchmarks so both measure the same bodies
+#[path = "../../benches/distributions.rs"]
+mod distributions;
+
+use distributions::{DISTRIBUTIONS, NUM_BODIES};
+
+const SAMPLE_SIZE: usize = 10;
+
+// The constants from sim.json with the given number of bodies
+fn constants(num_bodies: usize) -> Constants {
+    Constants {
+        num_bodies,
+        num_steps: 1,
+        write_interval: 1,
+        delta_t: 0.1,
+        softening: 0.1,
+        gravity: 1.0,
+        log_energy_conservation: false,
+        theta: 0.9,
+        position_log: PositionLogFormat::Raw,
+        npy_output: NpyOutput::None,
+        initial_conditions: None,
+        tracked_ids: Vec::new(),
+        removal: RemovalPolicy::default(),
+    }
+}
+
+fn positions(x: Vec<f64>, y: Vec<f64>) -> Vec2D {
+    Vec2D { x, y }
+}
+
+fn bench_construct(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cli/construct_tree");
+    group.sample_size(SAMPLE_SIZE);
+    for distribution in DISTRIBUTIONS {
+        for num_bodies in NUM_BODIES {
+            let (x, y) = distribution.generate(num_bodies);
+            let bodies: Vec2D = positions(x, y);
+            let constants: Constants = constants(num_bodies);
+            let mut tree: Tree = Tree::new(num_bodies);
+            group.bench_with_input(
+                BenchmarkId::new(distribution.name(), num_bodies),
+                &num_bodies,
+                |b, _| b.iter(|| construct_tree(&mut tree, &bodies, &constants)),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_acceleration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cli/add_node_acceleration");
+    group.sample_size(SAMPLE_SIZE);
+    for distribution in DISTRIBUTIONS {
+        for num_bodies in NUM_BODIES {
+            let (x, y) = distribution.generate(num_bodies);
+            let bodies: Vec2D = positions(x, y);
+            let constants: Constants = constants(num_bodies);
+            let mut tree: Tree = Tree::new(num_bodies);
+            construct_tree(&mut tree, &bodies, &constants);
+            group.bench_with_input(
+                BenchmarkId::new(distribution.name(), num_bodies),
+                &num_bodies,
+                |b, &num_bodies| {
+                    b.iter(|| {
+                        for i in 0..num_bodies {
+                            black_box(add_node_acceleration(
+                                (&bodies.x[i], &bodies.y[i]),
+                                &tree,
+                                ROOT_NODE_INDEX,
+                                tree.root_half_width,
+                                &constants,
+                            ));
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cli/step");
+    group.sample_size(SAMPLE_SIZE);
+    for distribution in DISTRIBUTIONS {
+        for num_bodies in NUM_BODIES {
+            let (x, y) = distribution.generate(num_bodies);
+            let bodies: Vec2D = positions(x, y);
+            let constants: Constants = constants(num_bodies);
+            // Every sample steps a fresh copy of the initial state
+            group.bench_with_input(
+                BenchmarkId::new(distribution.name(), num_bodies),
+                &num_bodies,
+                |b, &num_bodies| {
+                    b.iter_batched(
+                        || {
+                            (
+                                bodies.clone(),
+                                Vec2D::new(num_bodies),
+                                Vec2D::new(num_bodies),
+                                Tree::new(num_bodies),
+                                Timer::new(),
+                            )
+                        },
+                        |(mut bodies, mut velocities, mut accelerations, mut tree, mut timer)| {
+                            step(
+                                &mut bodies,
+                                &mut velocities,
+                                &mut accelerations,
+                                &mut tree,
+                                &constants,
+                                &mut timer,
+                            )
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_construct, bench_acceleration, bench_step);
+criterion_main!(benches);
@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::input::{self, parse_value};
+use crate::physics::logger::npy::NpyVelocityReader;
+use crate::physics::Vec2D;
+use crate::replay::{PositionLogReader, Snapshot};
+
+use self::csv::write_csv;
+use self::gadget::{read_gadget, write_gadget};
+use self::tipsy::{read_tipsy, write_tipsy};
+use self::vtk::{write_pvd, write_vtk};
+
+mod binary;
+pub mod csv;
+pub mod gadget;
+pub mod tipsy;
+pub mod vtk;
+
+// The formats snapshots can be converted to
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Vtk,
+    Csv,
+    #[serde(alias = "gadget")]
+    Gadget1,
+    Gadget2,
+    Tipsy,
+}
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "vtk" => Some(Format::Vtk),
+            "csv" => Some(Format::Csv),
+            "gadget1" | "gadget" => Some(Format::Gadget1),
+            "gadget2" => Some(Format::Gadget2),
+            "tipsy" => Some(Format::Tipsy),
+            _ => None,
+        }
+    }
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Vtk => "vtk",
+            Format::Csv => "csv",
+            Format::Gadget1 | Format::Gadget2 => "gadget",
+            Format::Tipsy => "tipsy",
+        }
+    }
+}
+
+// Reads a snapshot produced by another N-body code.
+// GADGET format 1 and 2 are told apart from the file itself.
+pub fn read_snapshot(format: Format, filename: &str) -> Result<Snapshot, String> {
+    match format {
+        Format::Gadget1 | Format::Gadget2 => read_gadget(filename),
+        Format::Tipsy => read_tipsy(filename),
+        Format::Vtk | Format::Csv => Err(format!(
+            "Snapshots can not be read from {} files",
+            format.extension()
+        )),
+    }
+}
+
+// Options for the convert subcommand
+pub struct ConvertOptions {
+    pub format: Format,
+    pub out_dir: Option<String>,
+    // Convert every stride-th frame of the position log
+    pub stride: usize,
+}
+impl ConvertOptions {
+    // Parses the arguments following the simulation name:
+    // --format <vtk|csv|gadget1|gadget2|tipsy> [--out <directory>] [--stride <frames>]
+    pub fn parse(args: &[String]) -> Result<ConvertOptions, String> {
+        let mut format: Option<Format> = None;
+        let mut out_dir: Option<String> = None;
+        let mut stride: usize = 1;
+        let mut i: usize = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--format" => {
+                    let name: String = parse_value(args, i + 1)?;
+                    format = match Format::from_name(&name) {
+                        Some(format) => Some(format),
+                        None => return Err(format!("Unknown format '{}'", name)),
+                    };
+                    i += 2;
+                }
+                "--out" => {
+                    out_dir = Some(parse_value(args, i + 1)?);
+                    i += 2;
+                }
+                "--stride" => {
+                    stride = parse_value(args, i + 1)?;
+                    i += 2;
+                }
+                other => return Err(format!("Unknown convert option '{}'", other)),
+            }
+        }
+        let format: Format = match format {
+            Some(format) => format,
+            None => return Err(String::from("Missing --format")),
+        };
+        if stride == 0 {
+            return Err(String::from("Frame stride must be at least 1"));
+        }
+        Ok(ConvertOptions {
+            format,
+            out_dir,
+            stride,
+        })
+    }
+}
+
+// Converts every stride-th frame of the position log to one file per frame in the
+// output directory. VTK output also gets a <sim name>.pvd series index.
+// Frames hold velocities if the run wrote "npy_output".
+pub fn convert(sim_name: &str, options: &ConvertOptions) -> Result<(), String> {
+    let constants: input::Constants = input::parse_input(&format!("{}.json", sim_name))?;
+    let mut reader: PositionLogReader = PositionLogReader::open(sim_name, &constants)?;
+    let out_dir: String = match &options.out_dir {
+        Some(out_dir) => out_dir.clone(),
+        None => format!("{}_{}", sim_name, options.format.extension()),
+    };
+    if let Err(err) = fs::create_dir_all(&out_dir) {
+        return Err(format!("Error creating '{}': {}", out_dir, err));
+    }
+    // Velocities come from the run's NumPy output, if it wrote any
+    let mut velocity_reader: Option<NpyVelocityReader> =
+        NpyVelocityReader::open(sim_name, constants.npy_output, constants.num_bodies)?;
+    let mut snapshot: Snapshot = Snapshot::new(constants.num_bodies);
+    snapshot.ids = reader.ids.clone();
+    // Every body is simulated with unit mass
+    snapshot.masses = Some(vec![1.0; constants.num_bodies]);
+    if velocity_reader.is_some() {
+        snapshot.velocities = Some(Vec2D::new(constants.num_bodies));
+    }
+    let mut frames: Vec<(f64, String)> = Vec::new();
+    let mut log_frame: usize = 0;
+    let mut max_error: f64 = 0.0;
+    while reader.read_frame(&mut snapshot.positions)? {
+        let skip: bool = !log_frame.is_multiple_of(options.stride);
+        snapshot.time = (log_frame * constants.write_interval) as f64 * constants.delta_t;
+        log_frame += 1;
+        if skip {
+            continue;
+        }
+        if let (Some(velocity_reader), Some(velocities)) =
+            (&mut velocity_reader, &mut snapshot.velocities)
+        {
+            velocity_reader.read_frame(log_frame - 1, velocities)?;
+        }
+        let (error_x, error_y): (f64, f64) = reader.max_error();
+        max_error = max_error.max(error_x).max(error_y);
+        let frame_filename: String =
+            format!("frame_{:05}.{}", frames.len(), options.format.extension());
+        let path: String = Path::new(&out_dir)
+            .join(&frame_filename)
+            .to_string_lossy()
+            .into_owned();
+        // Leave out bodies removed during the run
+        let present: Option<Snapshot> = snapshot
+            .positions
+            .x
+            .iter()
+            .any(|x| x.is_nan())
+            .then(|| snapshot.without_removed());
+        let written: &Snapshot = present.as_ref().unwrap_or(&snapshot);
+        match options.format {
+            Format::Vtk => write_vtk(written, &path)?,
+            Format::Csv => write_csv(written, &path)?,
+            Format::Gadget1 => write_gadget(written, &path, false)?,
+            Format::Gadget2 => write_gadget(written, &path, true)?,
+            Format::Tipsy => write_tipsy(written, &path, constants.softening)?,
+        }
+        frames.push((snapshot.time, frame_filename));
+    }
+    if let Format::Vtk = options.format {
+        // sim_name may include directories, which the index doesn't go in
+        let name: String = match Path::new(sim_name).file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => return Err(format!("'{}' does not end in a file name", sim_name)),
+        };
+        let pvd_path: String = Path::new(&out_dir)
+            .join(format!("{}.pvd", name))
+            .to_string_lossy()
+            .into_owned();
+        write_pvd(&frames, &pvd_path)?;
+    }
+    println!("Converted {} frames to {}", frames.len(), out_dir);
+    if max_error > 0.0 {
+        println!(
+            "Quantized positions are within {:e} of the simulation",
+            max_error
+        );
+    }
+    Ok(())
+}
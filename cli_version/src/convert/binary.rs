@@ -0,0 +1,89 @@
+// Helpers for reading and writing fixed-size binary fields of either endianness
+
+pub struct ByteReader<'a> {
+    data: &'a [u8],
+    pub position: usize,
+    pub big_endian: bool,
+}
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8], big_endian: bool) -> ByteReader<'a> {
+        ByteReader {
+            data,
+            position: 0,
+            big_endian,
+        }
+    }
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.remaining() < len {
+            return Err(format!(
+                "Unexpected end of file at byte {} (needed {} more bytes)",
+                self.position, len
+            ));
+        }
+        let bytes: &'a [u8] = &self.data[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array: [u8; N] = self.bytes(N)?.try_into().unwrap();
+        if self.big_endian {
+            array.reverse();
+        }
+        Ok(array)
+    }
+    pub fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+    pub fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+    pub fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+    pub fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+    pub fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+}
+
+pub struct ByteWriter {
+    pub data: Vec<u8>,
+    big_endian: bool,
+}
+impl ByteWriter {
+    pub fn new(big_endian: bool) -> ByteWriter {
+        ByteWriter {
+            data: Vec::new(),
+            big_endian,
+        }
+    }
+    fn push<const N: usize>(&mut self, mut array: [u8; N]) {
+        if self.big_endian {
+            array.reverse();
+        }
+        self.data.extend_from_slice(&array);
+    }
+    pub fn u32(&mut self, value: u32) {
+        self.push(value.to_le_bytes());
+    }
+    pub fn u64(&mut self, value: u64) {
+        self.push(value.to_le_bytes());
+    }
+    pub fn i32(&mut self, value: i32) {
+        self.push(value.to_le_bytes());
+    }
+    pub fn f32(&mut self, value: f32) {
+        self.push(value.to_le_bytes());
+    }
+    pub fn f64(&mut self, value: f64) {
+        self.push(value.to_le_bytes());
+    }
+    pub fn zeros(&mut self, len: usize) {
+        self.data.resize(self.data.len() + len, 0);
+    }
+}
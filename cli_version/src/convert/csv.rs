@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::replay::Snapshot;
+
+// Writes the snapshot as CSV with one row per body.
+// The vx, vy, mass and id columns are only written when the snapshot has them.
+pub fn write_csv(snapshot: &Snapshot, filename: &str) -> Result<(), String> {
+    let file: File = match File::create(filename) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+    };
+    let mut writer: BufWriter<File> = BufWriter::new(file);
+    match write_rows(snapshot, &mut writer) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+    }
+}
+
+fn write_rows(snapshot: &Snapshot, writer: &mut impl Write) -> std::io::Result<()> {
+    // Header
+    write!(writer, "index,x,y")?;
+    if snapshot.velocities.is_some() {
+        write!(writer, ",vx,vy")?;
+    }
+    if snapshot.masses.is_some() {
+        write!(writer, ",mass")?;
+    }
+    if snapshot.ids.is_some() {
+        write!(writer, ",id")?;
+    }
+    writeln!(writer)?;
+    // Rows
+    for i in 0..snapshot.num_bodies() {
+        write!(
+            writer,
+            "{},{},{}",
+            i, snapshot.positions.x[i], snapshot.positions.y[i]
+        )?;
+        if let Some(velocities) = &snapshot.velocities {
+            write!(writer, ",{},{}", velocities.x[i], velocities.y[i])?;
+        }
+        if let Some(masses) = &snapshot.masses {
+            write!(writer, ",{}", masses[i])?;
+        }
+        if let Some(ids) = &snapshot.ids {
+            write!(writer, ",{}", ids[i])?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,276 @@
+use std::fs;
+
+use crate::physics::Vec2D;
+use crate::replay::Snapshot;
+
+use super::binary::{ByteReader, ByteWriter};
+
+// GADGET snapshots are a sequence of Fortran unformatted records, each
+// surrounded by its length in bytes. Format 2 precedes every block with an
+// extra 8 byte record holding a 4 character label and the size of the block.
+const HEADER_SIZE: usize = 256;
+const NUM_TYPES: usize = 6;
+// Bodies are written as GADGET particle type 1 (halo)
+const BODY_TYPE: usize = 1;
+
+struct RecordReader<'a> {
+    bytes: ByteReader<'a>,
+    format2: bool,
+}
+impl<'a> RecordReader<'a> {
+    // Detects the endianness and format from the size of the first record
+    fn new(data: &'a [u8]) -> Result<RecordReader<'a>, String> {
+        for big_endian in [false, true] {
+            let first_record: usize = ByteReader::new(data, big_endian).u32()? as usize;
+            if first_record == HEADER_SIZE || first_record == 8 {
+                return Ok(RecordReader {
+                    bytes: ByteReader::new(data, big_endian),
+                    format2: first_record == 8,
+                });
+            }
+        }
+        Err(String::from("Not a GADGET format 1 or 2 snapshot"))
+    }
+    fn record(&mut self) -> Result<&'a [u8], String> {
+        let len: usize = self.bytes.u32()? as usize;
+        let record: &'a [u8] = self.bytes.bytes(len)?;
+        if self.bytes.u32()? as usize != len {
+            return Err(format!(
+                "Mismatched record length markers before byte {}",
+                self.bytes.position
+            ));
+        }
+        Ok(record)
+    }
+    // Reads the next block, skipping the format 2 label record
+    fn block(&mut self) -> Result<&'a [u8], String> {
+        if self.format2 {
+            self.record()?;
+        }
+        self.record()
+    }
+}
+
+// Reads a float array of num_values values stored as either f32 or f64
+fn read_floats(block: &[u8], num_values: usize, big_endian: bool) -> Result<Vec<f64>, String> {
+    let mut reader: ByteReader = ByteReader::new(block, big_endian);
+    // The block size bounds the allocation, whatever the header claims
+    let mut values: Vec<f64> = Vec::with_capacity(num_values.min(block.len() / 4));
+    if block.len() == num_values * 4 {
+        for _ in 0..num_values {
+            values.push(reader.f32()? as f64);
+        }
+    } else if block.len() == num_values * 8 {
+        for _ in 0..num_values {
+            values.push(reader.f64()?);
+        }
+    } else {
+        return Err(format!(
+            "Block of {} bytes does not hold {} floats",
+            block.len(),
+            num_values
+        ));
+    }
+    Ok(values)
+}
+
+// Reads a single-file GADGET format 1 or 2 snapshot of any endianness,
+// projecting positions and velocities onto the xy plane
+pub fn read_gadget(filename: &str) -> Result<Snapshot, String> {
+    let data: Vec<u8> = match fs::read(filename) {
+        Ok(data) => data,
+        Err(err) => return Err(format!("Error reading '{}': {}", filename, err)),
+    };
+    let mut records: RecordReader = RecordReader::new(&data)?;
+    let big_endian: bool = records.bytes.big_endian;
+    // Header
+    let mut header: ByteReader = ByteReader::new(records.block()?, big_endian);
+    let mut num_particles: [usize; NUM_TYPES] = [0; NUM_TYPES];
+    for count in num_particles.iter_mut() {
+        let header_count: i32 = header.i32()?;
+        if header_count < 0 {
+            return Err(format!(
+                "'{}' has a negative particle count {}",
+                filename, header_count
+            ));
+        }
+        *count = header_count as usize;
+    }
+    let mut type_masses: [f64; NUM_TYPES] = [0.0; NUM_TYPES];
+    for mass in type_masses.iter_mut() {
+        *mass = header.f64()?;
+    }
+    let time: f64 = header.f64()?;
+    // Skip redshift, flags and total particle counts to reach the number of files
+    header.bytes(8 + 4 + 4 + 4 * NUM_TYPES + 4)?;
+    if header.i32()? > 1 {
+        return Err(String::from(
+            "Multi-file GADGET snapshots are not supported",
+        ));
+    }
+    let num_bodies: usize = num_particles.iter().sum();
+    // Positions, velocities and ids
+    let positions: Vec<f64> = read_floats(records.block()?, 3 * num_bodies, big_endian)?;
+    let velocities: Vec<f64> = read_floats(records.block()?, 3 * num_bodies, big_endian)?;
+    let ids: Vec<u64> = read_ids(records.block()?, num_bodies, big_endian)?;
+    // Masses are in the header for types with a fixed mass and in the mass block for the rest
+    let num_variable: usize = (0..NUM_TYPES)
+        .filter(|&t| type_masses[t] == 0.0)
+        .map(|t| num_particles[t])
+        .sum();
+    let variable_masses: Vec<f64> = if num_variable > 0 {
+        read_floats(records.block()?, num_variable, big_endian)?
+    } else {
+        Vec::new()
+    };
+    let mut masses: Vec<f64> = Vec::with_capacity(num_bodies);
+    let mut next_variable: usize = 0;
+    for t in 0..NUM_TYPES {
+        for _ in 0..num_particles[t] {
+            if type_masses[t] == 0.0 {
+                masses.push(variable_masses[next_variable]);
+                next_variable += 1;
+            } else {
+                masses.push(type_masses[t]);
+            }
+        }
+    }
+    let mut snapshot: Snapshot = Snapshot::new(num_bodies);
+    let mut snapshot_velocities: Vec2D = Vec2D::new(num_bodies);
+    for i in 0..num_bodies {
+        snapshot.positions.x[i] = positions[3 * i];
+        snapshot.positions.y[i] = positions[3 * i + 1];
+        snapshot_velocities.x[i] = velocities[3 * i];
+        snapshot_velocities.y[i] = velocities[3 * i + 1];
+    }
+    snapshot.time = time;
+    snapshot.velocities = Some(snapshot_velocities);
+    snapshot.masses = Some(masses);
+    snapshot.ids = Some(ids);
+    Ok(snapshot)
+}
+
+// Reads an id block stored as either u32 or u64
+fn read_ids(block: &[u8], num_bodies: usize, big_endian: bool) -> Result<Vec<u64>, String> {
+    let mut reader: ByteReader = ByteReader::new(block, big_endian);
+    let mut ids: Vec<u64> = Vec::with_capacity(num_bodies.min(block.len() / 4));
+    if block.len() == num_bodies * 4 {
+        for _ in 0..num_bodies {
+            ids.push(reader.u32()? as u64);
+        }
+    } else if block.len() == num_bodies * 8 {
+        for _ in 0..num_bodies {
+            ids.push(reader.u64()?);
+        }
+    } else {
+        return Err(format!(
+            "Block of {} bytes does not hold {} ids",
+            block.len(),
+            num_bodies
+        ));
+    }
+    Ok(ids)
+}
+
+// Writes a little-endian GADGET format 1 or 2 snapshot with single precision
+// positions and velocities in the z = 0 plane. Missing velocities are written
+// as zero, missing masses as one and missing ids as the body index. Ids are
+// 32 bit unless one is too large.
+pub fn write_gadget(snapshot: &Snapshot, filename: &str, format2: bool) -> Result<(), String> {
+    let num_bodies: usize = snapshot.num_bodies();
+    if num_bodies > i32::MAX as usize {
+        return Err(format!("{} bodies is too many for GADGET", num_bodies));
+    }
+    let masses: Vec<f64> = match &snapshot.masses {
+        Some(masses) => masses.clone(),
+        None => vec![1.0; num_bodies],
+    };
+    // Use the header mass if every body has the same mass
+    let uniform_mass: Option<f64> = match masses.first() {
+        Some(&first) if masses.iter().all(|&mass| mass == first) && first != 0.0 => Some(first),
+        _ => None,
+    };
+    let mut writer: ByteWriter = ByteWriter::new(false);
+    // Header
+    let mut header: ByteWriter = ByteWriter::new(false);
+    for t in 0..NUM_TYPES {
+        header.i32(if t == BODY_TYPE { num_bodies as i32 } else { 0 });
+    }
+    for t in 0..NUM_TYPES {
+        header.f64(if t == BODY_TYPE {
+            uniform_mass.unwrap_or(0.0)
+        } else {
+            0.0
+        });
+    }
+    header.f64(snapshot.time);
+    // redshift, star formation and feedback flags
+    header.f64(0.0);
+    header.i32(0);
+    header.i32(0);
+    for t in 0..NUM_TYPES {
+        header.u32(if t == BODY_TYPE { num_bodies as u32 } else { 0 });
+    }
+    // cooling flag, number of files, box size, cosmology
+    header.i32(0);
+    header.i32(1);
+    header.zeros(4 * 8);
+    header.zeros(HEADER_SIZE - header.data.len());
+    write_block(&mut writer, b"HEAD", &header.data, format2);
+    // Positions
+    let mut block: ByteWriter = ByteWriter::new(false);
+    for i in 0..num_bodies {
+        block.f32(snapshot.positions.x[i] as f32);
+        block.f32(snapshot.positions.y[i] as f32);
+        block.f32(0.0);
+    }
+    write_block(&mut writer, b"POS ", &block.data, format2);
+    // Velocities
+    let mut block: ByteWriter = ByteWriter::new(false);
+    for i in 0..num_bodies {
+        match &snapshot.velocities {
+            Some(velocities) => {
+                block.f32(velocities.x[i] as f32);
+                block.f32(velocities.y[i] as f32);
+            }
+            None => block.zeros(8),
+        }
+        block.f32(0.0);
+    }
+    write_block(&mut writer, b"VEL ", &block.data, format2);
+    // Ids
+    let long_ids: bool = (0..num_bodies).any(|i| snapshot.id(i) > u32::MAX as u64);
+    let mut block: ByteWriter = ByteWriter::new(false);
+    for i in 0..num_bodies {
+        if long_ids {
+            block.u64(snapshot.id(i));
+        } else {
+            block.u32(snapshot.id(i) as u32);
+        }
+    }
+    write_block(&mut writer, b"ID  ", &block.data, format2);
+    // Masses
+    if uniform_mass.is_none() {
+        let mut block: ByteWriter = ByteWriter::new(false);
+        for mass in &masses {
+            block.f32(*mass as f32);
+        }
+        write_block(&mut writer, b"MASS", &block.data, format2);
+    }
+    match fs::write(filename, &writer.data) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+    }
+}
+
+fn write_block(writer: &mut ByteWriter, label: &[u8; 4], block: &[u8], format2: bool) {
+    if format2 {
+        writer.u32(8);
+        writer.data.extend_from_slice(label);
+        writer.u32(block.len() as u32 + 8);
+        writer.u32(8);
+    }
+    writer.u32(block.len() as u32);
+    writer.data.extend_from_slice(block);
+    writer.u32(block.len() as u32);
+}
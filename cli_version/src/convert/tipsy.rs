@@ -0,0 +1,150 @@
+use std::fs;
+
+use crate::physics::Vec2D;
+use crate::replay::Snapshot;
+
+use super::binary::{ByteReader, ByteWriter};
+
+// TIPSY binary snapshots: a header of time, body counts and dimensions
+// followed by the gas, dark and star particles as single precision floats.
+// Standard TIPSY files are big-endian with the header padded to 32 bytes.
+const HEADER_SIZE: usize = 28;
+const PADDED_HEADER_SIZE: usize = 32;
+// Floats per particle: mass, pos[3], vel[3] and then per-type fields
+const GAS_FLOATS: usize = 12;
+const DARK_FLOATS: usize = 9;
+const STAR_FLOATS: usize = 11;
+
+struct Header {
+    time: f64,
+    num_gas: usize,
+    num_dark: usize,
+    num_star: usize,
+}
+
+// Reads and validates the header with the given endianness,
+// returning it with the header size if it matches the file size
+fn read_header(data: &[u8], big_endian: bool) -> Option<(Header, usize)> {
+    let mut reader: ByteReader = ByteReader::new(data, big_endian);
+    let time: f64 = reader.f64().ok()?;
+    let num_bodies: i32 = reader.i32().ok()?;
+    let num_dimensions: i32 = reader.i32().ok()?;
+    let num_gas: i32 = reader.i32().ok()?;
+    let num_dark: i32 = reader.i32().ok()?;
+    let num_star: i32 = reader.i32().ok()?;
+    if num_gas < 0
+        || num_dark < 0
+        || num_star < 0
+        || num_bodies as i64 != num_gas as i64 + num_dark as i64 + num_star as i64
+    {
+        return None;
+    }
+    if !(1..=3).contains(&num_dimensions) {
+        return None;
+    }
+    let header: Header = Header {
+        time,
+        num_gas: num_gas as usize,
+        num_dark: num_dark as usize,
+        num_star: num_star as usize,
+    };
+    let particle_bytes: usize = 4
+        * (header.num_gas * GAS_FLOATS
+            + header.num_dark * DARK_FLOATS
+            + header.num_star * STAR_FLOATS);
+    for header_size in [PADDED_HEADER_SIZE, HEADER_SIZE] {
+        if data.len() == header_size + particle_bytes {
+            return Some((header, header_size));
+        }
+    }
+    None
+}
+
+// Reads a TIPSY binary snapshot of either endianness, with or without header
+// padding, projecting positions and velocities onto the xy plane
+pub fn read_tipsy(filename: &str) -> Result<Snapshot, String> {
+    let data: Vec<u8> = match fs::read(filename) {
+        Ok(data) => data,
+        Err(err) => return Err(format!("Error reading '{}': {}", filename, err)),
+    };
+    let (big_endian, header, header_size) = match read_header(&data, true) {
+        Some((header, header_size)) => (true, header, header_size),
+        None => match read_header(&data, false) {
+            Some((header, header_size)) => (false, header, header_size),
+            None => return Err(format!("'{}' is not a TIPSY snapshot", filename)),
+        },
+    };
+    let num_bodies: usize = header.num_gas + header.num_dark + header.num_star;
+    let mut reader: ByteReader = ByteReader::new(&data, big_endian);
+    reader.bytes(header_size)?;
+    let mut snapshot: Snapshot = Snapshot::new(num_bodies);
+    let mut velocities: Vec2D = Vec2D::new(num_bodies);
+    let mut masses: Vec<f64> = Vec::with_capacity(num_bodies);
+    let particle_types: [(usize, usize); 3] = [
+        (header.num_gas, GAS_FLOATS),
+        (header.num_dark, DARK_FLOATS),
+        (header.num_star, STAR_FLOATS),
+    ];
+    let mut i: usize = 0;
+    for (count, num_floats) in particle_types {
+        for _ in 0..count {
+            masses.push(reader.f32()? as f64);
+            snapshot.positions.x[i] = reader.f32()? as f64;
+            snapshot.positions.y[i] = reader.f32()? as f64;
+            reader.f32()?;
+            velocities.x[i] = reader.f32()? as f64;
+            velocities.y[i] = reader.f32()? as f64;
+            reader.f32()?;
+            // Skip the per-type fields
+            reader.bytes(4 * (num_floats - 7))?;
+            i += 1;
+        }
+    }
+    snapshot.time = header.time;
+    snapshot.velocities = Some(velocities);
+    snapshot.masses = Some(masses);
+    Ok(snapshot)
+}
+
+// Writes a standard big-endian TIPSY snapshot of dark particles in the z = 0
+// plane with the given softening. Missing velocities are written as zero and
+// missing masses as one.
+pub fn write_tipsy(snapshot: &Snapshot, filename: &str, softening: f64) -> Result<(), String> {
+    let num_bodies: usize = snapshot.num_bodies();
+    if num_bodies > i32::MAX as usize {
+        return Err(format!("{} bodies is too many for TIPSY", num_bodies));
+    }
+    let mut writer: ByteWriter = ByteWriter::new(true);
+    writer.f64(snapshot.time);
+    writer.i32(num_bodies as i32);
+    writer.i32(3);
+    writer.i32(0);
+    writer.i32(num_bodies as i32);
+    writer.i32(0);
+    writer.zeros(PADDED_HEADER_SIZE - HEADER_SIZE);
+    for i in 0..num_bodies {
+        let mass: f64 = match &snapshot.masses {
+            Some(masses) => masses[i],
+            None => 1.0,
+        };
+        writer.f32(mass as f32);
+        writer.f32(snapshot.positions.x[i] as f32);
+        writer.f32(snapshot.positions.y[i] as f32);
+        writer.f32(0.0);
+        match &snapshot.velocities {
+            Some(velocities) => {
+                writer.f32(velocities.x[i] as f32);
+                writer.f32(velocities.y[i] as f32);
+            }
+            None => writer.zeros(8),
+        }
+        writer.f32(0.0);
+        // softening and potential
+        writer.f32(softening as f32);
+        writer.f32(0.0);
+    }
+    match fs::write(filename, &writer.data) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+    }
+}
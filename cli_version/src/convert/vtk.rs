@@ -0,0 +1,87 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::replay::Snapshot;
+
+// Writes the snapshot as a binary legacy VTK polydata file with one vertex per body.
+// Bodies lie in the z = 0 plane. Legacy VTK binary data is big-endian.
+pub fn write_vtk(snapshot: &Snapshot, filename: &str) -> Result<(), String> {
+    let file: File = match File::create(filename) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+    };
+    let mut writer: BufWriter<File> = BufWriter::new(file);
+    match write_polydata(snapshot, &mut writer).and_then(|_| writer.flush()) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+    }
+}
+
+fn write_polydata(snapshot: &Snapshot, writer: &mut impl Write) -> std::io::Result<()> {
+    let num_bodies: usize = snapshot.num_bodies();
+    write!(
+        writer,
+        "# vtk DataFile Version 3.0\nspace-clicker snapshot t={}\nBINARY\nDATASET POLYDATA\n",
+        snapshot.time
+    )?;
+    // Positions
+    writeln!(writer, "POINTS {} double", num_bodies)?;
+    for i in 0..num_bodies {
+        writer.write_all(&snapshot.positions.x[i].to_be_bytes())?;
+        writer.write_all(&snapshot.positions.y[i].to_be_bytes())?;
+        writer.write_all(&0.0f64.to_be_bytes())?;
+    }
+    // One vertex cell per body so the points are rendered
+    writeln!(writer, "\nVERTICES {} {}", num_bodies, 2 * num_bodies)?;
+    for i in 0..num_bodies {
+        writer.write_all(&1i32.to_be_bytes())?;
+        writer.write_all(&(i as i32).to_be_bytes())?;
+    }
+    writeln!(writer)?;
+    if snapshot.velocities.is_none() && snapshot.masses.is_none() && snapshot.ids.is_none() {
+        return Ok(());
+    }
+    writeln!(writer, "POINT_DATA {}", num_bodies)?;
+    if let Some(velocities) = &snapshot.velocities {
+        writeln!(writer, "VECTORS velocity double")?;
+        for i in 0..num_bodies {
+            writer.write_all(&velocities.x[i].to_be_bytes())?;
+            writer.write_all(&velocities.y[i].to_be_bytes())?;
+            writer.write_all(&0.0f64.to_be_bytes())?;
+        }
+        writeln!(writer)?;
+    }
+    if let Some(masses) = &snapshot.masses {
+        writeln!(writer, "SCALARS mass double 1\nLOOKUP_TABLE default")?;
+        for mass in masses {
+            writer.write_all(&mass.to_be_bytes())?;
+        }
+        writeln!(writer)?;
+    }
+    if let Some(ids) = &snapshot.ids {
+        writeln!(writer, "SCALARS id vtktypeuint64 1\nLOOKUP_TABLE default")?;
+        for id in ids {
+            writer.write_all(&id.to_be_bytes())?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+// Writes a ParaView .pvd collection indexing the frame files by time
+pub fn write_pvd(frames: &[(f64, String)], filename: &str) -> Result<(), String> {
+    let mut pvd: String = String::from(
+        "<?xml version=\"1.0\"?>\n<VTKFile type=\"Collection\" version=\"0.1\">\n  <Collection>\n",
+    );
+    for (time, frame_filename) in frames {
+        pvd.push_str(&format!(
+            "    <DataSet timestep=\"{}\" part=\"0\" file=\"{}\"/>\n",
+            time, frame_filename
+        ));
+    }
+    pvd.push_str("  </Collection>\n</VTKFile>\n");
+    match std::fs::write(filename, pvd) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+    }
+}
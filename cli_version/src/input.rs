@@ -1,12 +1,16 @@
+use crate::convert::{read_snapshot, Format};
+use crate::physics::logger::compressed::PositionLogFormat;
+use crate::physics::logger::npy::NpyOutput;
+use crate::physics::removal::RemovalPolicy;
 use crate::physics::Vec2D;
+use crate::replay::Snapshot;
 use rand::Rng;
 use std::fs;
 
-use super::physics;
-
 use serde::Deserialize;
 
 const RANDOM_GENERATOR_BOX_SIZE: f64 = 100.0;
+#[allow(dead_code)]
 const RANDOM_GENERATOR_START_TEMP: f64 = 100.0; // Higher is colder
 
 // Input structure to store JSON input file data
@@ -20,6 +24,23 @@ pub struct Constants {
     pub gravity: f64,
     pub log_energy_conservation: bool,
     pub theta: f64,
+    #[serde(default)]
+    pub position_log: PositionLogFormat,
+    #[serde(default)]
+    pub npy_output: NpyOutput,
+    #[serde(default)]
+    pub initial_conditions: Option<InitialConditions>,
+    #[serde(default)]
+    pub tracked_ids: Vec<u64>,
+    #[serde(default)]
+    pub removal: RemovalPolicy,
+}
+
+// A snapshot from another N-body code to start the simulation from
+#[derive(Debug, Deserialize)]
+pub struct InitialConditions {
+    pub format: Format,
+    pub file: String,
 }
 
 // Opens the input JSON file and parses the data into an Input struct
@@ -33,6 +54,11 @@ pub struct Constants {
 // 	"gravity": 1.0,
 // 	"log_energy_conservation": false,
 // 	"theta": 0.5,
+// 	"position_log": "raw", (optional: "raw", "lossless" or { "quantized": <bits> })
+// 	"npy_output": "none", (optional: "none", "npz" or "stacked")
+// 	"initial_conditions": { "format": "tipsy", "file": "ic.std" }, (optional: "gadget" or "tipsy")
+// 	"tracked_ids": [0, 42], (optional: bodies whose state is written to <sim name>_trajectories.csv every step)
+// 	"removal": { "max_distance": 1000.0, "remove_unbound": true }, (optional: both default to off, removed bodies are NaN in later frames)
 // }
 pub fn parse_input(filename: &str) -> Result<Constants, String> {
     // Convert file to String
@@ -51,8 +77,19 @@ pub fn parse_input(filename: &str) -> Result<Constants, String> {
     Ok(constant)
 }
 
+// Parses the argument at index as a value of type T
+pub fn parse_value<T: std::str::FromStr>(args: &[String], index: usize) -> Result<T, String> {
+    match args.get(index) {
+        Some(arg) => match arg.parse::<T>() {
+            Ok(value) => Ok(value),
+            Err(_) => Err(format!("Invalid value '{}'", arg)),
+        },
+        None => Err(format!("Missing value after '{}'", args[index - 1])),
+    }
+}
+
 // Adds random points to the given mutable reference to a vector of points
-pub fn initialize_bodies(positions: &mut Vec2D, velocities: &mut Vec2D, num_bodies: usize) {
+pub fn initialize_bodies(positions: &mut Vec2D, _velocities: &mut Vec2D, num_bodies: usize) {
     // Generate random positions in a uniform distribution from -RANDOM_GENERATOR_BOX_SIZE/2 to RANDOM_GENERATOR_BOX_SIZE/2
     let mut rng = rand::thread_rng();
     for i in 0..num_bodies {
@@ -67,3 +104,37 @@ pub fn initialize_bodies(positions: &mut Vec2D, velocities: &mut Vec2D, num_bodi
     // 	rng.gen::<f64>() * RANDOM_GENERATOR_BOX_SIZE / RANDOM_GENERATOR_START_TEMP - RANDOM_GENERATOR_BOX_SIZE / (2.0 * RANDOM_GENERATOR_START_TEMP);
     // }
 }
+
+// Sets the positions, velocities and ids from the initial conditions snapshot,
+// projected onto the xy plane. Bodies without ids are numbered by index.
+pub fn load_initial_conditions(
+    initial_conditions: &InitialConditions,
+    positions: &mut Vec2D,
+    velocities: &mut Vec2D,
+    ids: &mut Vec<u64>,
+    num_bodies: usize,
+) -> Result<(), String> {
+    let snapshot: Snapshot = read_snapshot(initial_conditions.format, &initial_conditions.file)?;
+    if snapshot.num_bodies() != num_bodies {
+        return Err(format!(
+            "'{}' has {} bodies but num_bodies is {}",
+            initial_conditions.file,
+            snapshot.num_bodies(),
+            num_bodies
+        ));
+    }
+    if let Some(masses) = &snapshot.masses {
+        if masses.iter().any(|&mass| mass != masses[0]) {
+            println!(
+                "Warning: '{}' has unequal masses but all bodies are simulated with equal mass",
+                initial_conditions.file
+            );
+        }
+    }
+    *ids = (0..num_bodies).map(|i| snapshot.id(i)).collect();
+    *positions = snapshot.positions;
+    if let Some(snapshot_velocities) = snapshot.velocities {
+        *velocities = snapshot_velocities;
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod convert;
+pub mod input;
+pub mod physics;
+pub mod render;
+pub mod replay;
+pub mod simulation;
+pub mod timing;
@@ -1,28 +1,34 @@
-mod input;
-mod physics;
-
-use devtimer::DevTime;
 use std::env;
-use std::fs::File;
+use std::process;
 
-use crate::input::initialize_bodies;
-use crate::physics::logger::{log_energy, log_positions};
-use crate::physics::Vec2D;
+use space_clicker::convert::{self, ConvertOptions};
+use space_clicker::input;
+use space_clicker::render::{self, RenderOptions};
+use space_clicker::simulation::{Simulation, SimulationBuilder};
+use space_clicker::timing::TimingReport;
 
 // Starts a particle simulation with the given input JSON file
-// and outputs the result to a binary file.
+// and outputs the result to a binary file. Exits with status 1 if the run fails.
 fn main() {
-    let mut devtime = DevTime::new_simple();
-    devtime.start();
     // Check arguments
     let args: Vec<String> = env::args().collect();
-    if args.len() < 1 {
+    let program: &str = args.first().map_or("space-clicker", String::as_str);
+    if args.len() > 1 && (args[1] == "--help" || args[1] == "-h") {
         println!(
-            "Usage: {} <simulation name>\nRequired files: <simulation name>.json",
-            args[0]
+            "Usage: {} [simulation name]\n       {} render <simulation name> [options]\n       {} convert <simulation name> --format <format> [options]\nRequired files: <simulation name>.json, named sim unless given",
+            program, program, program
         );
         return;
     }
+    // Subcommands
+    if args.len() > 1 && args[1] == "render" {
+        render_command(&args);
+        return;
+    }
+    if args.len() > 1 && args[1] == "convert" {
+        convert_command(&args);
+        return;
+    }
     // sim name: "sim" unless specified by user
     let sim_name: String = match args.len() {
         1 => String::from("sim"),
@@ -34,53 +40,87 @@ fn main() {
             Ok(input) => input,
             Err(err) => {
                 println!("Error parsing input file: {}", err);
-                return;
+                process::exit(1);
+            }
+        };
+    let num_steps: usize = constants.num_steps;
+    // Initialize the bodies, create the output files and start writing them in the background
+    let mut simulation: Simulation =
+        match SimulationBuilder::new(constants).output(&sim_name).build() {
+            Ok(simulation) => simulation,
+            Err(err) => {
+                println!("Error setting up simulation: {}", err);
+                process::exit(1);
             }
         };
-    // Open the position log file
-    let mut position_log: File = match File::create(sim_name.clone() + ".bin") {
-        Ok(file) => file,
+    // Run the simulation
+    for _ in 0..num_steps {
+        if let Err(err) = simulation.step() {
+            println!("Error writing output: {}", err);
+            process::exit(1);
+        }
+    }
+    // Wait for the remaining output to be written
+    if let Err(err) = simulation.finish() {
+        println!("Error writing output: {}", err);
+        process::exit(1);
+    }
+    if simulation.num_removed() > 0 {
+        println!(
+            "Removed {} bodies, {} remain",
+            simulation.num_removed(),
+            simulation.num_bodies()
+        );
+    }
+    // Print timing results
+    let report: TimingReport = simulation.timing_report();
+    report.print();
+    if let Err(err) = report.write((sim_name + "_timing.json").as_str()) {
+        println!("Error writing timing report: {}", err);
+        process::exit(1);
+    }
+}
+
+// Renders the position log of a finished simulation to PNG frames
+fn render_command(args: &[String]) {
+    if args.len() < 3 {
+        println!(
+            "Usage: {} render <simulation name> [--size <width> <height>] [--zoom <multiplier>] [--fixed <centre x> <centre y> <width>] [--out <directory>] [--density] [--colour-map <grey|inferno|viridis|stars>] [--scaling <linear|log|asinh>] [--smoothing <pixels>] [--stride <frames>] [--gif <file>] [--fps <frames per second>]\nRequired files: <simulation name>.json, <simulation name>.bin or <simulation name>.binz",
+            args[0]
+        );
+        process::exit(1);
+    }
+    let options: RenderOptions = match RenderOptions::parse(&args[3..]) {
+        Ok(options) => options,
         Err(err) => {
-            println!("Error creating position log file: {}", err);
-            return;
+            println!("Error parsing render options: {}", err);
+            process::exit(1);
         }
     };
-    // Open the energy log file
-    let mut energy_log: File = match File::create(sim_name.clone() + "_energy.csv") {
-        Ok(file) => file,
+    if let Err(err) = render::render(&args[2], &options) {
+        println!("Error rendering: {}", err);
+        process::exit(1);
+    }
+}
+
+// Converts the position log of a finished simulation to files for external tools
+fn convert_command(args: &[String]) {
+    if args.len() < 3 {
+        println!(
+            "Usage: {} convert <simulation name> --format <vtk|csv|gadget1|gadget2|tipsy> [--out <directory>] [--stride <frames>]\nRequired files: <simulation name>.json, <simulation name>.bin or <simulation name>.binz",
+            args[0]
+        );
+        process::exit(1);
+    }
+    let options: ConvertOptions = match ConvertOptions::parse(&args[3..]) {
+        Ok(options) => options,
         Err(err) => {
-            println!("Error creating energy log file: {}", err);
-            return;
+            println!("Error parsing convert options: {}", err);
+            process::exit(1);
         }
     };
-    // Initialize the positions, velocities, and accelerations
-    let mut positions: Vec2D = Vec2D::new(constants.num_bodies);
-    let mut velocities: Vec2D = Vec2D::new(constants.num_bodies);
-    let mut accelerations: Vec2D = Vec2D::new(constants.num_bodies); // I think this data can be stored in "positions" when used
-    initialize_bodies(&mut positions, &mut velocities, constants.num_bodies);
-    // Initialize the Barnes-Hut tree
-    let mut bh_tree: physics::bh_tree::Tree = physics::bh_tree::Tree::new(constants.num_bodies);
-    // Run the simulation
-    for step in 0..constants.num_steps {
-        // Log the energy
-        if constants.log_energy_conservation {
-            log_energy(&positions, &velocities, &constants, &mut energy_log)
-        }
-        // Log the positions
-        if step % constants.write_interval == 0 {
-            log_positions(&positions, &constants, &mut position_log);
-        }
-        // Step forward in time
-        physics::step(
-            &mut positions,
-            &mut velocities,
-            &mut accelerations,
-            &mut bh_tree,
-            &constants,
-        );
+    if let Err(err) = convert::convert(&args[2], &options) {
+        println!("Error converting: {}", err);
+        process::exit(1);
     }
-    // Finish timing
-    devtime.stop();
-    // Print timing results
-    println!("Time: {} us", devtime.time_in_micros().unwrap());
 }
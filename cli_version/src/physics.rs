@@ -1,7 +1,9 @@
 pub mod bh_tree;
 pub mod logger;
+pub mod removal;
 
 use crate::input::Constants;
+use crate::timing::{Phase, Timer};
 
 use self::bh_tree::Tree;
 
@@ -15,6 +17,7 @@ impl Point {
     }
 }
 
+#[derive(Clone)]
 pub struct Vec2D {
     pub x: Vec<f64>,
     pub y: Vec<f64>,
@@ -28,7 +31,7 @@ impl Vec2D {
     }
 }
 
-fn add_node_acceleration(
+pub fn add_node_acceleration(
     body: (&f64, &f64),
     bh_tree: &Tree,
     node_index: usize,
@@ -111,56 +114,119 @@ fn add_node_acceleration(
     acceleration
 }
 
-// Takes a mutable reference to a vector of position points and the input constants and moves each point
-pub fn step(
-    positions: &mut Vec2D,
-    velocities: &mut Vec2D,
+// How the acceleration of each body is calculated
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Solver {
+    // Barnes-Hut tree approximation, with accuracy set by theta
+    #[default]
+    BarnesHut,
+    // Exact sum over every pair of bodies, O(n^2)
+    Direct,
+}
+
+// How velocities and positions are advanced each step
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Integrator {
+    // Semi-implicit Euler: kick with the acceleration at the start of the step, then drift
+    #[default]
+    Euler,
+    // Kick-drift-kick leapfrog, which is second order and time reversible
+    Leapfrog,
+}
+
+// Calculates the acceleration of every body due to gravity with the given solver
+pub fn calc_accelerations(
+    positions: &Vec2D,
     accelerations: &mut Vec2D,
     bh_tree: &mut Tree,
     constants: &Constants,
+    solver: Solver,
+    timer: &mut Timer,
 ) {
-    // Construct the Barnes-Hut tree
-    bh_tree::construct_tree(bh_tree, positions, constants);
-    // bh_tree::print_tree(bh_tree, 0, 0);
-    // // close program
-    // std::process::exit(0);
-    // SIMD
-    for i in 0..constants.num_bodies {
-        // Zero the acceleration
-        accelerations.x[i] = 0.0;
-        accelerations.y[i] = 0.0;
-    }
-
-    // not SIMD
-    for i in 0..constants.num_bodies {
-        // Calculate the acceleration
-        let (acc_x, acc_y) = add_node_acceleration(
-            (&positions.x[i], &positions.y[i]),
-            bh_tree,
-            bh_tree::ROOT_NODE_INDEX,
-            bh_tree.root_half_width,
-            constants,
-        );
-        accelerations.x[i] = acc_x;
-        accelerations.y[i] = acc_y;
+    match solver {
+        Solver::BarnesHut => {
+            // Construct the Barnes-Hut tree
+            timer.start(Phase::TreeBuild);
+            bh_tree::construct_tree(bh_tree, positions, constants);
+            timer.start(Phase::ForceWalk);
+            for i in 0..constants.num_bodies {
+                // Calculate the acceleration
+                let (acc_x, acc_y) = add_node_acceleration(
+                    (&positions.x[i], &positions.y[i]),
+                    bh_tree,
+                    bh_tree::ROOT_NODE_INDEX,
+                    bh_tree.root_half_width,
+                    constants,
+                );
+                accelerations.x[i] = acc_x;
+                accelerations.y[i] = acc_y;
+            }
+        }
+        Solver::Direct => {
+            timer.start(Phase::ForceWalk);
+            for i in 0..constants.num_bodies {
+                let mut acceleration: (f64, f64) = (0.0, 0.0);
+                for j in 0..constants.num_bodies {
+                    if i != j {
+                        // Same softened force law as the tree leaves (mass is 1)
+                        let dx: f64 = positions.x[j] - positions.x[i];
+                        let dy: f64 = positions.y[j] - positions.y[i];
+                        let d: f64 = (dx * dx + dy * dy).sqrt();
+                        acceleration.0 += dx / (d * d * d + constants.softening);
+                        acceleration.1 += dy / (d * d * d + constants.softening);
+                    }
+                }
+                accelerations.x[i] = acceleration.0;
+                accelerations.y[i] = acceleration.1;
+            }
+        }
     }
-
     // SIMD
     for i in 0..constants.num_bodies {
         // Multiply the acceleration by gravity
         accelerations.x[i] *= constants.gravity;
         accelerations.y[i] *= constants.gravity;
     }
+}
+
+// Updates the velocities by the accelerations over dt
+pub fn kick(velocities: &mut Vec2D, accelerations: &Vec2D, dt: f64) {
     // SIMD
-    for i in 0..constants.num_bodies {
-        // Update the velocity
-        velocities.x[i] += accelerations.x[i] * constants.delta_t;
-        velocities.y[i] += accelerations.y[i] * constants.delta_t;
+    for i in 0..velocities.x.len() {
+        velocities.x[i] += accelerations.x[i] * dt;
+        velocities.y[i] += accelerations.y[i] * dt;
     }
+}
+
+// Updates the positions by the velocities over dt
+pub fn drift(positions: &mut Vec2D, velocities: &Vec2D, dt: f64) {
     // SIMD
-    for i in 0..constants.num_bodies {
-        // Update the position
-        positions.x[i] += velocities.x[i] * constants.delta_t;
-        positions.y[i] += velocities.y[i] * constants.delta_t;
+    for i in 0..positions.x.len() {
+        positions.x[i] += velocities.x[i] * dt;
+        positions.y[i] += velocities.y[i] * dt;
     }
 }
+
+// Takes a mutable reference to a vector of position points and the input constants and moves each point
+// with the Barnes-Hut solver and semi-implicit Euler integrator
+pub fn step(
+    positions: &mut Vec2D,
+    velocities: &mut Vec2D,
+    accelerations: &mut Vec2D,
+    bh_tree: &mut Tree,
+    constants: &Constants,
+    timer: &mut Timer,
+) {
+    calc_accelerations(
+        positions,
+        accelerations,
+        bh_tree,
+        constants,
+        Solver::BarnesHut,
+        timer,
+    );
+    timer.start(Phase::Integration);
+    kick(velocities, accelerations, constants.delta_t);
+    drift(positions, velocities, constants.delta_t);
+    timer.stop();
+}
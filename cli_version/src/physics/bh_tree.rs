@@ -41,6 +41,7 @@ impl NodeDesc {
             half_width,
         }
     }
+    #[allow(dead_code)]
     pub fn clone(&self) -> NodeDesc {
         NodeDesc {
             index: self.index,
@@ -237,7 +238,7 @@ fn insert(tree: &mut Tree, body: InsertBody, node_desc: &NodeDesc) {
             let quadrant_as_body = InsertBody::Existing {
                 tree_index: node_desc.index,
             };
-            let child_desc: NodeDesc = get_child(tree, &quadrant_as_body, &node_desc);
+            let child_desc: NodeDesc = get_child(tree, &quadrant_as_body, node_desc);
             insert(tree, quadrant_as_body, &child_desc);
             // Re-try adding new particle to the tree
             insert(tree, body, node_desc);
@@ -256,7 +257,7 @@ fn insert(tree: &mut Tree, body: InsertBody, node_desc: &NodeDesc) {
                 / (tree.nodes[node_desc.index].mass + 1) as f64;
         tree.nodes[node_desc.index].mass += 1;
         // Add the particle to the appropriate child
-        let child_desc: NodeDesc = get_child(tree, &body, &node_desc);
+        let child_desc: NodeDesc = get_child(tree, &body, node_desc);
         // println!("Inserting into child node {}", child_desc.index);
         insert(tree, body, &child_desc);
     }
@@ -311,6 +312,7 @@ pub fn construct_tree(tree: &mut Tree, bodies: &Vec2D, constants: &Constants) {
 }
 
 // Print the Barnes-Hut tree to the console
+#[allow(dead_code)]
 pub fn print_tree(tree: &Tree, depth: usize, node_index: usize) {
     if node_index == 0 && depth != 0 {
         return;
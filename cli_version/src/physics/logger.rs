@@ -1,19 +1,29 @@
-use std::fs::File;
+pub mod background;
+pub mod compressed;
+pub mod npy;
+
 use std::io::Write;
 
-use crate::physics::Point;
 use crate::input::Constants;
 
 use super::Vec2D;
 
 
-// Logs the energy conservation given positions, velocities, and a file to write to.
+// The energy of the system at one step
+#[derive(Clone, Copy)]
+pub struct Energy {
+	pub total: f64,
+	pub kinetic: f64,
+	pub potential: f64,
+}
+
+// Calculates the energy conservation given positions and velocities.
 //
 // The energy conservation is defined as the sum of the kinetic energy and the potential energy.
 // The kinetic energy is defined as 1/2 * v^2
 // The potential energy is defined as -G / r
 // The total energy is defined as the sum of the kinetic energy and the potential energy
-pub fn log_energy(positions: &Vec2D, velocities: &Vec2D, constants: &Constants, file: &mut File) {
+pub fn calc_energy(positions: &Vec2D, velocities: &Vec2D, constants: &Constants) -> Energy {
 	// Calculate the total energy
 	let mut total_kinetic_energy: f64 = 0.0;
 	let mut total_potential_energy: f64 = 0.0;
@@ -32,28 +42,40 @@ pub fn log_energy(positions: &Vec2D, velocities: &Vec2D, constants: &Constants,
 		total_kinetic_energy += kinetic_energy;
 		total_potential_energy += potential_energy;
 	}
-	// Calculate the total energy
-	let total_energy: f64 = total_kinetic_energy + total_potential_energy;
-	// Save the total energy, kinetic energy and potential energy to the text file
-	match file.write(format!("{},{},{}\n", total_energy, total_kinetic_energy, total_potential_energy).as_bytes()) {
-		Ok(_) => (),
-		Err(err) => panic!("Error writing to energy log file: {}", err),
+	Energy {
+		total: total_kinetic_energy + total_potential_energy,
+		kinetic: total_kinetic_energy,
+		potential: total_potential_energy,
+	}
+}
+
+// Logs the total energy, kinetic energy and potential energy as a line of the energy log
+pub fn log_energy(energy: &Energy, file: &mut impl Write) -> Result<(), String> {
+	match file.write_all(format!("{},{},{}\n", energy.total, energy.kinetic, energy.potential).as_bytes()) {
+		Ok(_) => Ok(()),
+		Err(err) => Err(format!("Error writing to energy log file: {}", err)),
 	}
 }
 
 // Logs the positions of the bodies to a binary file
-pub fn log_positions(positions: &Vec2D, constants: &Constants, file: &mut File) {
+pub fn log_positions(positions: &Vec2D, file: &mut impl Write) -> Result<(), String> {
 	// Write the positions
-	for i in 0..constants.num_bodies {
-		match file.write(&positions.x[i].to_le_bytes()) {
-			Ok(_) => (),
-			Err(err) => panic!("Error writing to position log file: {}", err),
+	for axis in [&positions.x, &positions.y] {
+		for value in axis {
+			if let Err(err) = file.write_all(&value.to_le_bytes()) {
+				return Err(format!("Error writing to position log file: {}", err));
+			}
 		}
 	}
-	for i in 0..constants.num_bodies {
-		match file.write(&positions.y[i].to_le_bytes()) {
-			Ok(_) => (),
-			Err(err) => panic!("Error writing to position log file: {}", err),
+	Ok(())
+}
+
+// Logs the id of the body in each frame slot to a binary file, as little-endian u64s
+pub fn log_ids(ids: &[u64], file: &mut impl Write) -> Result<(), String> {
+	for id in ids {
+		if let Err(err) = file.write_all(&id.to_le_bytes()) {
+			return Err(format!("Error writing to id file: {}", err));
 		}
 	}
-}
\ No newline at end of file
+	file.flush().map_err(|err| format!("Error writing to id file: {}", err))
+}
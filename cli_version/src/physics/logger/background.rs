@@ -0,0 +1,338 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use crate::input::Constants;
+use crate::physics::removal::RemovalReason;
+use crate::physics::Vec2D;
+
+use super::compressed::{CompressedLogger, PositionLogFormat};
+use super::npy::NpyLogger;
+use super::{log_energy, log_ids, log_positions, Energy};
+
+// How many messages may wait for the writer thread before the simulation
+// blocks. Each message holds at most one copied frame, which bounds the memory
+// used when the disk falls behind.
+const CHANNEL_CAPACITY: usize = 4;
+
+// The id, position and velocity of one body
+pub struct BodyState {
+    pub id: u64,
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+}
+impl BodyState {
+    pub fn new(index: usize, positions: &Vec2D, velocities: &Vec2D, ids: &[u64]) -> BodyState {
+        BodyState {
+            id: ids[index],
+            x: positions.x[index],
+            y: positions.y[index],
+            vx: velocities.x[index],
+            vy: velocities.y[index],
+        }
+    }
+}
+
+enum Message {
+    Snapshot {
+        positions: Vec2D,
+        velocities: Vec2D,
+        // The frame slot of each body, if any have been removed
+        slots: Option<Vec<usize>>,
+    },
+    Energy(Energy),
+    // The tracked bodies at one step
+    Trajectory {
+        step: usize,
+        time: f64,
+        bodies: Vec<BodyState>,
+    },
+    // The bodies removed at one step
+    Removed {
+        step: usize,
+        time: f64,
+        bodies: Vec<(BodyState, RemovalReason)>,
+    },
+}
+
+// Every output file of a run, owned by the writer thread
+struct Outputs {
+    position_log: Option<BufWriter<File>>,
+    energy_log: BufWriter<File>,
+    trajectory_log: Option<BufWriter<File>>,
+    removal_log: Option<BufWriter<File>>,
+    compressed_log: CompressedLogger,
+    npy_logger: NpyLogger,
+    // The ids of the bodies in each frame slot, which are those at the start of the run
+    frame_ids: Vec<u64>,
+}
+impl Outputs {
+    fn write(&mut self, message: Message) -> Result<(), String> {
+        match message {
+            Message::Snapshot {
+                positions,
+                velocities,
+                slots,
+            } => {
+                // Frames keep every body of the run, with NaN for removed bodies
+                let (positions, velocities): (Vec2D, Vec2D) = match slots {
+                    Some(slots) => (
+                        into_frame(&positions, &slots, self.frame_ids.len()),
+                        into_frame(&velocities, &slots, self.frame_ids.len()),
+                    ),
+                    None => (positions, velocities),
+                };
+                if let Some(position_log) = &mut self.position_log {
+                    log_positions(&positions, position_log)?;
+                }
+                self.compressed_log.log(&positions)?;
+                self.npy_logger
+                    .log(&positions, &velocities, &self.frame_ids)
+            }
+            Message::Energy(energy) => log_energy(&energy, &mut self.energy_log),
+            Message::Trajectory { step, time, bodies } => {
+                if let Some(trajectory_log) = &mut self.trajectory_log {
+                    for body in bodies {
+                        if let Err(err) = writeln!(
+                            trajectory_log,
+                            "{},{},{},{},{},{},{}",
+                            step, time, body.id, body.x, body.y, body.vx, body.vy
+                        ) {
+                            return Err(format!("Error writing to trajectory file: {}", err));
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Message::Removed { step, time, bodies } => {
+                if let Some(removal_log) = &mut self.removal_log {
+                    for (body, reason) in bodies {
+                        if let Err(err) = writeln!(
+                            removal_log,
+                            "{},{},{},{},{},{},{},{}",
+                            step,
+                            time,
+                            body.id,
+                            body.x,
+                            body.y,
+                            body.vx,
+                            body.vy,
+                            reason.name()
+                        ) {
+                            return Err(format!("Error writing to removal log file: {}", err));
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+    fn finish(&mut self) -> Result<(), String> {
+        if let Some(position_log) = &mut self.position_log {
+            if let Err(err) = position_log.flush() {
+                return Err(format!("Error writing to position log file: {}", err));
+            }
+        }
+        if let Err(err) = self.energy_log.flush() {
+            return Err(format!("Error writing to energy log file: {}", err));
+        }
+        if let Some(trajectory_log) = &mut self.trajectory_log {
+            if let Err(err) = trajectory_log.flush() {
+                return Err(format!("Error writing to trajectory file: {}", err));
+            }
+        }
+        if let Some(removal_log) = &mut self.removal_log {
+            if let Err(err) = removal_log.flush() {
+                return Err(format!("Error writing to removal log file: {}", err));
+            }
+        }
+        self.compressed_log.finish()?;
+        self.npy_logger.finish()
+    }
+}
+
+// Places each body's values in its frame slot, leaving NaN in the slots of removed bodies
+fn into_frame(values: &Vec2D, slots: &[usize], frame_size: usize) -> Vec2D {
+    let mut frame: Vec2D = Vec2D {
+        x: vec![f64::NAN; frame_size],
+        y: vec![f64::NAN; frame_size],
+    };
+    for (i, &slot) in slots.iter().enumerate() {
+        frame.x[slot] = values.x[i];
+        frame.y[slot] = values.y[i];
+    }
+    frame
+}
+
+// Creates a CSV file with the given header, if it is wanted
+fn create_csv(
+    wanted: bool,
+    filename: &str,
+    header: &str,
+    description: &str,
+) -> Result<Option<BufWriter<File>>, String> {
+    if !wanted {
+        return Ok(None);
+    }
+    match File::create(filename) {
+        Ok(file) => {
+            let mut writer: BufWriter<File> = BufWriter::new(file);
+            if let Err(err) = writeln!(writer, "{}", header) {
+                return Err(format!("Error writing to {}: {}", description, err));
+            }
+            Ok(Some(writer))
+        }
+        Err(err) => Err(format!("Error creating {}: {}", description, err)),
+    }
+}
+
+// Writes snapshots and energies on a background thread so the simulation
+// only waits on the disk when the channel is full.
+//
+// A write error stops the thread, and is returned by the next call to
+// log_snapshot, log_energy or finish.
+pub struct BackgroundWriter {
+    sender: Option<SyncSender<Message>>,
+    handle: Option<JoinHandle<Result<(), String>>>,
+    // The number of bodies in each frame
+    frame_size: usize,
+}
+impl BackgroundWriter {
+    // Creates the output files for a run of the bodies with the given ids and starts the writer thread
+    pub fn spawn(
+        sim_name: &str,
+        constants: &Constants,
+        ids: &[u64],
+    ) -> Result<BackgroundWriter, String> {
+        let position_log: Option<BufWriter<File>> = match constants.position_log {
+            PositionLogFormat::Raw => match File::create(format!("{}.bin", sim_name)) {
+                Ok(file) => Some(BufWriter::new(file)),
+                Err(err) => return Err(format!("Error creating position log file: {}", err)),
+            },
+            _ => None,
+        };
+        let energy_log: BufWriter<File> = match File::create(format!("{}_energy.csv", sim_name)) {
+            Ok(file) => BufWriter::new(file),
+            Err(err) => return Err(format!("Error creating energy log file: {}", err)),
+        };
+        // Frames hold the bodies in the order of their ids, which replays read back
+        match File::create(format!("{}_ids.bin", sim_name)) {
+            Ok(file) => log_ids(ids, &mut BufWriter::new(file))?,
+            Err(err) => return Err(format!("Error creating id file: {}", err)),
+        }
+        // Only created when there are bodies to track or remove
+        let trajectory_log: Option<BufWriter<File>> = create_csv(
+            !constants.tracked_ids.is_empty(),
+            &format!("{}_trajectories.csv", sim_name),
+            "step,time,id,x,y,vx,vy",
+            "trajectory file",
+        )?;
+        let removal_log: Option<BufWriter<File>> = create_csv(
+            constants.removal.is_enabled(),
+            &format!("{}_removed.csv", sim_name),
+            "step,time,id,x,y,vx,vy,reason",
+            "removal log file",
+        )?;
+        let mut outputs: Outputs = Outputs {
+            position_log,
+            energy_log,
+            trajectory_log,
+            removal_log,
+            compressed_log: CompressedLogger::create(
+                sim_name,
+                constants.position_log,
+                constants.num_bodies,
+            )?,
+            npy_logger: NpyLogger::create(sim_name, constants.npy_output, constants.num_bodies)?,
+            frame_ids: ids.to_vec(),
+        };
+        let (sender, receiver): (SyncSender<Message>, Receiver<Message>) =
+            mpsc::sync_channel(CHANNEL_CAPACITY);
+        let handle = thread::Builder::new()
+            .name(String::from("snapshot writer"))
+            .spawn(move || {
+                for message in receiver {
+                    outputs.write(message)?;
+                }
+                outputs.finish()
+            });
+        match handle {
+            Ok(handle) => Ok(BackgroundWriter {
+                sender: Some(sender),
+                handle: Some(handle),
+                frame_size: ids.len(),
+            }),
+            Err(err) => Err(format!("Error starting writer thread: {}", err)),
+        }
+    }
+    // Queues a copy of the positions and velocities to be written. slots gives
+    // the frame slot of each body, which is its index until bodies are removed.
+    pub fn log_snapshot(
+        &mut self,
+        positions: &Vec2D,
+        velocities: &Vec2D,
+        slots: &[usize],
+    ) -> Result<(), String> {
+        let all_bodies: bool = slots.len() == self.frame_size;
+        self.send(Message::Snapshot {
+            positions: positions.clone(),
+            velocities: velocities.clone(),
+            slots: (!all_bodies).then(|| slots.to_vec()),
+        })
+    }
+    // Queues the state of the tracked bodies
+    pub fn log_trajectory(
+        &mut self,
+        step: usize,
+        time: f64,
+        bodies: Vec<BodyState>,
+    ) -> Result<(), String> {
+        self.send(Message::Trajectory { step, time, bodies })
+    }
+    // Queues the state of the removed bodies and why they were removed
+    pub fn log_removed(
+        &mut self,
+        step: usize,
+        time: f64,
+        bodies: Vec<(BodyState, RemovalReason)>,
+    ) -> Result<(), String> {
+        self.send(Message::Removed { step, time, bodies })
+    }
+    pub fn log_energy(&mut self, energy: Energy) -> Result<(), String> {
+        self.send(Message::Energy(energy))
+    }
+    fn send(&mut self, message: Message) -> Result<(), String> {
+        let sent: bool = match &self.sender {
+            Some(sender) => sender.send(message).is_ok(),
+            None => false,
+        };
+        match sent {
+            true => Ok(()),
+            // The thread only hangs up after an error
+            false => self.finish(),
+        }
+    }
+    // Waits for everything queued to be written and the files to be flushed
+    pub fn finish(&mut self) -> Result<(), String> {
+        // Dropping the sender ends the thread's loop once the queue is empty
+        self.sender = None;
+        match self.handle.take() {
+            Some(handle) => match handle.join() {
+                Ok(result) => result,
+                Err(_) => Err(String::from("Writer thread panicked")),
+            },
+            None => Err(String::from("Writer thread has already finished")),
+        }
+    }
+}
+impl Drop for BackgroundWriter {
+    // Writes anything still queued if finish was never called
+    fn drop(&mut self) {
+        if self.handle.is_some() {
+            let _ = self.finish();
+        }
+    }
+}
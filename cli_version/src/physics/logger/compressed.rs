@@ -0,0 +1,461 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+
+use crate::physics::Vec2D;
+
+// Compressed position logs start with a header of the magic, the format
+// version, the mode, the bit depth, a reserved byte and the number of bodies
+// as a little-endian u64. Each frame follows as a little-endian u32 length and
+// a zlib stream holding:
+//  - quantized: f64 min and step for x then y, followed by the change in each
+//    body's quantized x then y since the previous frame
+//  - lossless: the change in the bit pattern of each body's x then y since
+//    the previous frame
+// Changes are zigzag encoded varints, with the first frame relative to zero.
+const MAGIC: &[u8; 4] = b"SCPL";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 16;
+const MODE_LOSSLESS: u8 = 0;
+const MODE_QUANTIZED: u8 = 1;
+const MAX_BITS: u32 = 32;
+
+// How positions are logged, set by "position_log" in the input JSON
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionLogFormat {
+    // Uncompressed f64 positions in <sim name>.bin
+    #[default]
+    Raw,
+    // Exact positions compressed into <sim name>.binz
+    Lossless,
+    // Positions quantized to the given number of bits per axis relative to
+    // each frame's bounding box, compressed into <sim name>.binz
+    Quantized(u32),
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> Result<u64, String> {
+    let mut value: u64 = 0;
+    let mut shift: u32 = 0;
+    loop {
+        let byte: u8 = match data.get(*position) {
+            Some(&byte) => byte,
+            None => return Err(String::from("Truncated compressed frame")),
+        };
+        *position += 1;
+        if shift >= 64 {
+            return Err(String::from("Invalid varint in compressed frame"));
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+fn read_f64(data: &[u8], position: &mut usize) -> Result<f64, String> {
+    match data.get(*position..*position + 8) {
+        Some(bytes) => {
+            *position += 8;
+            Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        None => Err(String::from("Truncated compressed frame")),
+    }
+}
+
+// The quantized value of a NaN (removed) body, one past the highest level
+fn missing_level(bits: u32) -> i64 {
+    1i64 << bits
+}
+
+// Returns the minimum and step that quantize values onto 2^bits - 1 levels.
+// NaN values are skipped, as they mark removed bodies.
+// The step is zero when every value is the same.
+fn quantization(values: &[f64], bits: u32) -> Result<(f64, f64), String> {
+    let mut min: f64 = f64::INFINITY;
+    let mut max: f64 = f64::NEG_INFINITY;
+    for &value in values {
+        if value.is_nan() {
+            continue;
+        }
+        if !value.is_finite() {
+            return Err(format!("Cannot quantize non-finite position {}", value));
+        }
+        min = min.min(value);
+        max = max.max(value);
+    }
+    if min > max {
+        return Ok((0.0, 0.0));
+    }
+    let levels: f64 = ((1u64 << bits) - 1) as f64;
+    Ok((min, (max - min) / levels))
+}
+
+// Writes positions to <sim name>.binz in the configured compressed format
+pub struct CompressedLogger {
+    writer: Option<BufWriter<File>>,
+    filename: String,
+    format: PositionLogFormat,
+    // Quantized positions or position bit patterns of the previous frame
+    previous_x: Vec<i64>,
+    previous_y: Vec<i64>,
+    payload: Vec<u8>,
+}
+impl CompressedLogger {
+    // Creates the log file, or does nothing for the raw format
+    pub fn create(
+        sim_name: &str,
+        format: PositionLogFormat,
+        num_bodies: usize,
+    ) -> Result<CompressedLogger, String> {
+        let filename: String = format!("{}.binz", sim_name);
+        let (mode, bits): (u8, u32) = match format {
+            PositionLogFormat::Raw => {
+                return Ok(CompressedLogger {
+                    writer: None,
+                    filename,
+                    format,
+                    previous_x: Vec::new(),
+                    previous_y: Vec::new(),
+                    payload: Vec::new(),
+                })
+            }
+            PositionLogFormat::Lossless => (MODE_LOSSLESS, 64),
+            PositionLogFormat::Quantized(bits) => {
+                if !(1..=MAX_BITS).contains(&bits) {
+                    return Err(format!(
+                        "Quantization must use between 1 and {} bits, not {}",
+                        MAX_BITS, bits
+                    ));
+                }
+                (MODE_QUANTIZED, bits)
+            }
+        };
+        let file: File = match File::create(&filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+        };
+        let mut writer: BufWriter<File> = BufWriter::new(file);
+        let mut header: Vec<u8> = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&[VERSION, mode, bits as u8, 0]);
+        header.extend_from_slice(&(num_bodies as u64).to_le_bytes());
+        if let Err(err) = writer.write_all(&header) {
+            return Err(format!("Error writing '{}': {}", filename, err));
+        }
+        Ok(CompressedLogger {
+            writer: Some(writer),
+            filename,
+            format,
+            previous_x: vec![0; num_bodies],
+            previous_y: vec![0; num_bodies],
+            payload: Vec::new(),
+        })
+    }
+    pub fn log(&mut self, positions: &Vec2D) -> Result<(), String> {
+        let writer: &mut BufWriter<File> = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        self.payload.clear();
+        match self.format {
+            PositionLogFormat::Raw => (),
+            PositionLogFormat::Lossless => {
+                encode_lossless(&positions.x, &mut self.previous_x, &mut self.payload);
+                encode_lossless(&positions.y, &mut self.previous_y, &mut self.payload);
+            }
+            PositionLogFormat::Quantized(bits) => {
+                let (min_x, step_x): (f64, f64) = quantization(&positions.x, bits)?;
+                let (min_y, step_y): (f64, f64) = quantization(&positions.y, bits)?;
+                for value in [min_x, step_x, min_y, step_y] {
+                    self.payload.extend_from_slice(&value.to_le_bytes());
+                }
+                encode_quantized(
+                    &positions.x,
+                    min_x,
+                    step_x,
+                    missing_level(bits),
+                    &mut self.previous_x,
+                    &mut self.payload,
+                );
+                encode_quantized(
+                    &positions.y,
+                    min_y,
+                    step_y,
+                    missing_level(bits),
+                    &mut self.previous_y,
+                    &mut self.payload,
+                );
+            }
+        }
+        let mut encoder: ZlibEncoder<Vec<u8>> =
+            ZlibEncoder::new(Vec::new(), Compression::default());
+        let compressed: Vec<u8> = match encoder
+            .write_all(&self.payload)
+            .and_then(|_| encoder.finish())
+        {
+            Ok(compressed) => compressed,
+            Err(err) => return Err(format!("Error compressing '{}': {}", self.filename, err)),
+        };
+        let result = writer
+            .write_all(&(compressed.len() as u32).to_le_bytes())
+            .and_then(|_| writer.write_all(&compressed));
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", self.filename, err)),
+        }
+    }
+    // Flushes the log, must be called once the run is over
+    pub fn finish(&mut self) -> Result<(), String> {
+        if let Some(writer) = &mut self.writer {
+            if let Err(err) = writer.flush() {
+                return Err(format!("Error writing '{}': {}", self.filename, err));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn encode_lossless(values: &[f64], previous: &mut [i64], payload: &mut Vec<u8>) {
+    for (value, previous) in values.iter().zip(previous.iter_mut()) {
+        let bits: i64 = value.to_bits() as i64;
+        write_varint(payload, zigzag(bits.wrapping_sub(*previous)));
+        *previous = bits;
+    }
+}
+
+fn encode_quantized(
+    values: &[f64],
+    min: f64,
+    step: f64,
+    missing: i64,
+    previous: &mut [i64],
+    payload: &mut Vec<u8>,
+) {
+    for (value, previous) in values.iter().zip(previous.iter_mut()) {
+        let quantized: i64 = if value.is_nan() {
+            missing
+        } else if step > 0.0 {
+            ((value - min) / step).round() as i64
+        } else {
+            0
+        };
+        write_varint(payload, zigzag(quantized - *previous));
+        *previous = quantized;
+    }
+}
+
+// Reads the frames of a compressed position log written by CompressedLogger
+pub struct CompressedLogReader {
+    reader: BufReader<File>,
+    filename: String,
+    quantized: bool,
+    // The quantized value of removed bodies, unused for lossless logs
+    missing: i64,
+    pub num_frames: usize,
+    frames_read: usize,
+    previous_x: Vec<i64>,
+    previous_y: Vec<i64>,
+    compressed: Vec<u8>,
+    payload: Vec<u8>,
+    // Quantization step of the last frame read, zero for lossless logs
+    step: (f64, f64),
+}
+impl CompressedLogReader {
+    pub fn open(filename: &str, num_bodies: usize) -> Result<CompressedLogReader, String> {
+        let file: File = match File::open(filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error opening '{}': {}", filename, err)),
+        };
+        let mut reader: BufReader<File> = BufReader::new(file);
+        let mut header: [u8; HEADER_SIZE] = [0; HEADER_SIZE];
+        if let Err(err) = reader.read_exact(&mut header) {
+            return Err(format!("Error reading '{}': {}", filename, err));
+        }
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(format!("'{}' is not a compressed position log", filename));
+        }
+        let quantized: bool = match header[5] {
+            MODE_LOSSLESS => false,
+            MODE_QUANTIZED => true,
+            mode => return Err(format!("'{}' has unknown mode {}", filename, mode)),
+        };
+        // Lossless logs store 64 bits, which have no missing level as NaN is kept exactly
+        let bits: u32 = header[6] as u32;
+        let missing: i64 = match quantized {
+            true if (1..=MAX_BITS).contains(&bits) => missing_level(bits),
+            true => return Err(format!("'{}' has invalid bit depth {}", filename, bits)),
+            false => 0,
+        };
+        let logged_bodies: u64 = u64::from_le_bytes(header[8..].try_into().unwrap());
+        if logged_bodies != num_bodies as u64 {
+            return Err(format!(
+                "'{}' holds {} bodies but the simulation has {}",
+                filename, logged_bodies, num_bodies
+            ));
+        }
+        // Count the complete frames, ignoring any partial frame left by an interrupted run
+        let file_size: u64 = match reader.get_ref().metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(err) => return Err(format!("Error reading '{}': {}", filename, err)),
+        };
+        let mut num_frames: usize = 0;
+        let mut offset: u64 = HEADER_SIZE as u64;
+        let mut length: [u8; 4] = [0; 4];
+        while offset + 4 <= file_size {
+            if let Err(err) = reader.read_exact(&mut length) {
+                return Err(format!("Error reading '{}': {}", filename, err));
+            }
+            let frame_end: u64 = offset + 4 + u32::from_le_bytes(length) as u64;
+            if frame_end > file_size {
+                break;
+            }
+            if let Err(err) = reader.seek(SeekFrom::Start(frame_end)) {
+                return Err(format!("Error reading '{}': {}", filename, err));
+            }
+            offset = frame_end;
+            num_frames += 1;
+        }
+        if let Err(err) = reader.seek(SeekFrom::Start(HEADER_SIZE as u64)) {
+            return Err(format!("Error reading '{}': {}", filename, err));
+        }
+        Ok(CompressedLogReader {
+            reader,
+            filename: filename.to_string(),
+            quantized,
+            missing,
+            num_frames,
+            frames_read: 0,
+            previous_x: vec![0; num_bodies],
+            previous_y: vec![0; num_bodies],
+            compressed: Vec::new(),
+            payload: Vec::new(),
+            step: (0.0, 0.0),
+        })
+    }
+    // Reads the next frame into positions, returning false once all frames have been read
+    pub fn read_frame(&mut self, positions: &mut Vec2D) -> Result<bool, String> {
+        if self.frames_read == self.num_frames {
+            return Ok(false);
+        }
+        let mut length: [u8; 4] = [0; 4];
+        if let Err(err) = self.reader.read_exact(&mut length) {
+            return Err(format!("Error reading '{}': {}", self.filename, err));
+        }
+        self.compressed
+            .resize(u32::from_le_bytes(length) as usize, 0);
+        if let Err(err) = self.reader.read_exact(&mut self.compressed) {
+            return Err(format!("Error reading '{}': {}", self.filename, err));
+        }
+        self.payload.clear();
+        if let Err(err) = ZlibDecoder::new(&self.compressed[..]).read_to_end(&mut self.payload) {
+            return Err(format!(
+                "Error decompressing frame {} of '{}': {}",
+                self.frames_read, self.filename, err
+            ));
+        }
+        let mut position: usize = 0;
+        if self.quantized {
+            let min_x: f64 = read_f64(&self.payload, &mut position)?;
+            let step_x: f64 = read_f64(&self.payload, &mut position)?;
+            let min_y: f64 = read_f64(&self.payload, &mut position)?;
+            let step_y: f64 = read_f64(&self.payload, &mut position)?;
+            decode_quantized(
+                &self.payload,
+                &mut position,
+                min_x,
+                step_x,
+                self.missing,
+                &mut self.previous_x,
+                &mut positions.x,
+            )?;
+            decode_quantized(
+                &self.payload,
+                &mut position,
+                min_y,
+                step_y,
+                self.missing,
+                &mut self.previous_y,
+                &mut positions.y,
+            )?;
+            self.step = (step_x, step_y);
+        } else {
+            decode_lossless(
+                &self.payload,
+                &mut position,
+                &mut self.previous_x,
+                &mut positions.x,
+            )?;
+            decode_lossless(
+                &self.payload,
+                &mut position,
+                &mut self.previous_y,
+                &mut positions.y,
+            )?;
+        }
+        self.frames_read += 1;
+        Ok(true)
+    }
+    // The largest difference between a logged and a reconstructed position
+    // along each axis in the last frame read: half a quantization step, or
+    // zero for lossless logs
+    pub fn max_error(&self) -> (f64, f64) {
+        (self.step.0 / 2.0, self.step.1 / 2.0)
+    }
+}
+
+fn decode_lossless(
+    payload: &[u8],
+    position: &mut usize,
+    previous: &mut [i64],
+    values: &mut Vec<f64>,
+) -> Result<(), String> {
+    values.clear();
+    for previous in previous.iter_mut() {
+        *previous = previous.wrapping_add(unzigzag(read_varint(payload, position)?));
+        values.push(f64::from_bits(*previous as u64));
+    }
+    Ok(())
+}
+
+fn decode_quantized(
+    payload: &[u8],
+    position: &mut usize,
+    min: f64,
+    step: f64,
+    missing: i64,
+    previous: &mut [i64],
+    values: &mut Vec<f64>,
+) -> Result<(), String> {
+    values.clear();
+    for previous in previous.iter_mut() {
+        *previous = previous.wrapping_add(unzigzag(read_varint(payload, position)?));
+        if *previous == missing {
+            values.push(f64::NAN);
+        } else {
+            values.push(min + *previous as f64 * step);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,461 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use serde::Deserialize;
+
+use crate::physics::Vec2D;
+
+// Stacked array headers are padded to this length so the shape can be rewritten in place
+const STACKED_HEADER_LEN: usize = 128;
+const NPY_ALIGNMENT: usize = 64;
+const NPY_MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+// How snapshots are written as NumPy arrays, set by "npy_output" in the input JSON
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NpyOutput {
+    // No NumPy output
+    #[default]
+    None,
+    // One <sim name>_npz/frame_<n>.npz per snapshot holding positions, velocities, masses and ids
+    Npz,
+    // One <sim name>_<array>.npy per array with the snapshots stacked along the first axis
+    Stacked,
+}
+
+// Builds a version 1.0 .npy header for an array of the given dtype and shape,
+// padded with spaces to at least min_len bytes and a multiple of 64
+fn npy_header(descr: &str, shape: &[usize], min_len: usize) -> Vec<u8> {
+    let shape: String = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|dimension| dimension.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    };
+    let dict: String = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    // magic + header length + dict + newline
+    let unpadded: usize = NPY_MAGIC.len() + 2 + dict.len() + 1;
+    let total: usize = unpadded.max(min_len).div_ceil(NPY_ALIGNMENT) * NPY_ALIGNMENT;
+    let mut header: Vec<u8> = Vec::with_capacity(total);
+    header.extend_from_slice(NPY_MAGIC);
+    header.extend_from_slice(&((total - NPY_MAGIC.len() - 2) as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(total - 1, b' ');
+    header.push(b'\n');
+    header
+}
+
+// The arrays written for each snapshot, as (name, dtype, shape, little-endian data)
+fn snapshot_arrays(
+    positions: &Vec2D,
+    velocities: &Vec2D,
+    ids: &[u64],
+    num_bodies: usize,
+) -> [(&'static str, &'static str, Vec<usize>, Vec<u8>); 4] {
+    let mut position_data: Vec<u8> = Vec::with_capacity(num_bodies * 16);
+    let mut velocity_data: Vec<u8> = Vec::with_capacity(num_bodies * 16);
+    let mut mass_data: Vec<u8> = Vec::with_capacity(num_bodies * 8);
+    let mut id_data: Vec<u8> = Vec::with_capacity(num_bodies * 8);
+    for i in 0..num_bodies {
+        position_data.extend_from_slice(&positions.x[i].to_le_bytes());
+        position_data.extend_from_slice(&positions.y[i].to_le_bytes());
+        velocity_data.extend_from_slice(&velocities.x[i].to_le_bytes());
+        velocity_data.extend_from_slice(&velocities.y[i].to_le_bytes());
+        // All bodies have unit mass
+        mass_data.extend_from_slice(&1.0f64.to_le_bytes());
+    }
+    for id in ids {
+        id_data.extend_from_slice(&id.to_le_bytes());
+    }
+    [
+        ("positions", "<f8", vec![num_bodies, 2], position_data),
+        ("velocities", "<f8", vec![num_bodies, 2], velocity_data),
+        ("masses", "<f8", vec![num_bodies], mass_data),
+        ("ids", "<u8", vec![num_bodies], id_data),
+    ]
+}
+
+// A .npy file that grows by one snapshot at a time along its first axis
+struct StackedArray {
+    writer: BufWriter<File>,
+    filename: String,
+    descr: &'static str,
+    frame_shape: Vec<usize>,
+    num_frames: usize,
+}
+impl StackedArray {
+    fn create(
+        filename: String,
+        descr: &'static str,
+        frame_shape: Vec<usize>,
+    ) -> Result<StackedArray, String> {
+        let file: File = match File::create(&filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+        };
+        let mut array: StackedArray = StackedArray {
+            writer: BufWriter::new(file),
+            filename,
+            descr,
+            frame_shape,
+            num_frames: 0,
+        };
+        array.write_header()?;
+        Ok(array)
+    }
+    fn write_header(&mut self) -> Result<(), String> {
+        let mut shape: Vec<usize> = vec![self.num_frames];
+        shape.extend_from_slice(&self.frame_shape);
+        let header: Vec<u8> = npy_header(self.descr, &shape, STACKED_HEADER_LEN);
+        if header.len() != STACKED_HEADER_LEN {
+            return Err(format!("Array shape {:?} is too large", shape));
+        }
+        let result = self
+            .writer
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.writer.write_all(&header))
+            .and_then(|_| self.writer.seek(SeekFrom::End(0)))
+            .map(|_| ());
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", self.filename, err)),
+        }
+    }
+    fn append(&mut self, data: &[u8]) -> Result<(), String> {
+        if let Err(err) = self.writer.write_all(data) {
+            return Err(format!("Error writing '{}': {}", self.filename, err));
+        }
+        self.num_frames += 1;
+        Ok(())
+    }
+    // Rewrites the header with the final number of frames
+    fn finish(&mut self) -> Result<(), String> {
+        self.write_header()?;
+        match self.writer.flush() {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", self.filename, err)),
+        }
+    }
+}
+
+// Writes snapshots as NumPy arrays in the configured layout
+pub struct NpyLogger {
+    output: NpyOutput,
+    out_dir: String,
+    num_bodies: usize,
+    num_frames: usize,
+    stacked: Vec<StackedArray>,
+}
+impl NpyLogger {
+    pub fn create(
+        sim_name: &str,
+        output: NpyOutput,
+        num_bodies: usize,
+    ) -> Result<NpyLogger, String> {
+        let out_dir: String = format!("{}_npz", sim_name);
+        let mut stacked: Vec<StackedArray> = Vec::new();
+        match output {
+            NpyOutput::None => (),
+            NpyOutput::Npz => {
+                if let Err(err) = fs::create_dir_all(&out_dir) {
+                    return Err(format!("Error creating '{}': {}", out_dir, err));
+                }
+            }
+            NpyOutput::Stacked => {
+                let empty: Vec2D = Vec2D::new(num_bodies);
+                let empty_ids: Vec<u64> = vec![0; num_bodies];
+                for (name, descr, shape, _) in
+                    snapshot_arrays(&empty, &empty, &empty_ids, num_bodies)
+                {
+                    stacked.push(StackedArray::create(
+                        format!("{}_{}.npy", sim_name, name),
+                        descr,
+                        shape,
+                    )?);
+                }
+            }
+        }
+        Ok(NpyLogger {
+            output,
+            out_dir,
+            num_bodies,
+            num_frames: 0,
+            stacked,
+        })
+    }
+    pub fn log(
+        &mut self,
+        positions: &Vec2D,
+        velocities: &Vec2D,
+        ids: &[u64],
+    ) -> Result<(), String> {
+        match self.output {
+            NpyOutput::None => return Ok(()),
+            NpyOutput::Npz => {
+                let mut npz: ZipWriter = ZipWriter::new();
+                for (name, descr, shape, data) in
+                    snapshot_arrays(positions, velocities, ids, self.num_bodies)
+                {
+                    let mut npy: Vec<u8> = npy_header(descr, &shape, 0);
+                    npy.extend_from_slice(&data);
+                    npz.add_file(&format!("{}.npy", name), &npy)?;
+                }
+                let filename: String = format!("{}/frame_{:05}.npz", self.out_dir, self.num_frames);
+                npz.write(&filename)?;
+            }
+            NpyOutput::Stacked => {
+                let arrays = snapshot_arrays(positions, velocities, ids, self.num_bodies);
+                for (array, (_, _, _, data)) in self.stacked.iter_mut().zip(arrays) {
+                    array.append(&data)?;
+                }
+            }
+        }
+        self.num_frames += 1;
+        Ok(())
+    }
+    // Completes any stacked arrays, must be called once the run is over
+    pub fn finish(&mut self) -> Result<(), String> {
+        for array in &mut self.stacked {
+            array.finish()?;
+        }
+        Ok(())
+    }
+}
+
+// Reads the velocities of each frame back from the NumPy output of a run
+pub struct NpyVelocityReader {
+    source: VelocitySource,
+    num_bodies: usize,
+}
+enum VelocitySource {
+    // <sim name>_velocities.npy and the length of its header
+    Stacked(BufReader<File>, u64),
+    // The <sim name>_npz directory
+    Npz(String),
+}
+impl NpyVelocityReader {
+    // Opens the velocities written by an NpyLogger with the given output, or
+    // returns None if the run wrote none
+    pub fn open(
+        sim_name: &str,
+        output: NpyOutput,
+        num_bodies: usize,
+    ) -> Result<Option<NpyVelocityReader>, String> {
+        let source: VelocitySource = match output {
+            NpyOutput::None => return Ok(None),
+            NpyOutput::Npz => VelocitySource::Npz(format!("{}_npz", sim_name)),
+            NpyOutput::Stacked => {
+                let filename: String = format!("{}_velocities.npy", sim_name);
+                let file: File = match File::open(&filename) {
+                    Ok(file) => file,
+                    Err(err) => return Err(format!("Error opening '{}': {}", filename, err)),
+                };
+                let mut reader: BufReader<File> = BufReader::new(file);
+                let mut header: Vec<u8> = vec![0; STACKED_HEADER_LEN];
+                if let Err(err) = reader.read_exact(&mut header) {
+                    return Err(format!("Error reading '{}': {}", filename, err));
+                }
+                let header_len: usize = npy_data_offset(&header, &filename)?;
+                VelocitySource::Stacked(reader, header_len as u64)
+            }
+        };
+        Ok(Some(NpyVelocityReader { source, num_bodies }))
+    }
+    // Reads the velocities of the given frame
+    pub fn read_frame(&mut self, frame: usize, velocities: &mut Vec2D) -> Result<(), String> {
+        let frame_size: usize = self.num_bodies * 16;
+        let data: Vec<u8> = match &mut self.source {
+            VelocitySource::Stacked(reader, header_len) => {
+                let mut data: Vec<u8> = vec![0; frame_size];
+                let offset: u64 = *header_len + (frame * frame_size) as u64;
+                let result = reader
+                    .seek(SeekFrom::Start(offset))
+                    .and_then(|_| reader.read_exact(&mut data));
+                if let Err(err) = result {
+                    return Err(format!(
+                        "Error reading velocities of frame {}: {}",
+                        frame, err
+                    ));
+                }
+                data
+            }
+            VelocitySource::Npz(out_dir) => {
+                let filename: String = format!("{}/frame_{:05}.npz", out_dir, frame);
+                let npz: Vec<u8> = match fs::read(&filename) {
+                    Ok(npz) => npz,
+                    Err(err) => return Err(format!("Error reading '{}': {}", filename, err)),
+                };
+                let npy: &[u8] = zip_file(&npz, "velocities.npy", &filename)?;
+                let data: &[u8] = &npy[npy_data_offset(npy, &filename)?..];
+                if data.len() != frame_size {
+                    return Err(format!(
+                        "'{}' does not hold the velocities of {} bodies",
+                        filename, self.num_bodies
+                    ));
+                }
+                data.to_vec()
+            }
+        };
+        velocities.x.clear();
+        velocities.y.clear();
+        for bytes in data.chunks_exact(16) {
+            velocities
+                .x
+                .push(f64::from_le_bytes(bytes[..8].try_into().unwrap()));
+            velocities
+                .y
+                .push(f64::from_le_bytes(bytes[8..].try_into().unwrap()));
+        }
+        Ok(())
+    }
+}
+
+// Checks the start of a .npy file written by npy_header holds doubles and
+// returns where its data starts
+fn npy_data_offset(npy: &[u8], filename: &str) -> Result<usize, String> {
+    let start: usize = NPY_MAGIC.len() + 2;
+    if npy.len() < start || &npy[..NPY_MAGIC.len()] != NPY_MAGIC {
+        return Err(format!("'{}' is not a version 1.0 .npy array", filename));
+    }
+    let offset: usize = start + u16::from_le_bytes([npy[start - 2], npy[start - 1]]) as usize;
+    let dict: &[u8] = &npy[start..offset.min(npy.len())];
+    if !dict.starts_with(b"{'descr': '<f8'") {
+        return Err(format!(
+            "'{}' does not hold little-endian doubles",
+            filename
+        ));
+    }
+    Ok(offset)
+}
+
+// Finds a file in a stored zip archive written by ZipWriter
+fn zip_file<'a>(zip: &'a [u8], name: &str, filename: &str) -> Result<&'a [u8], String> {
+    let mut position: usize = 0;
+    // Local file headers are 30 bytes before the name
+    while zip.len() >= position + 30 && zip[position..position + 4] == 0x04034b50u32.to_le_bytes() {
+        let field = |offset: usize| -> usize {
+            u16::from_le_bytes([zip[position + offset], zip[position + offset + 1]]) as usize
+        };
+        let size: usize =
+            u32::from_le_bytes(zip[position + 18..position + 22].try_into().unwrap()) as usize;
+        let name_start: usize = position + 30;
+        let data_start: usize = name_start + field(26) + field(28);
+        if zip.len() < data_start + size {
+            break;
+        }
+        if &zip[name_start..name_start + field(26)] == name.as_bytes() {
+            return Ok(&zip[data_start..data_start + size]);
+        }
+        position = data_start + size;
+    }
+    Err(format!("'{}' has no {}", filename, name))
+}
+
+// Writes uncompressed (stored) zip archives, which is all .npz needs
+struct ZipWriter {
+    data: Vec<u8>,
+    central_directory: Vec<u8>,
+    num_files: u16,
+}
+impl ZipWriter {
+    fn new() -> ZipWriter {
+        ZipWriter {
+            data: Vec::new(),
+            central_directory: Vec::new(),
+            num_files: 0,
+        }
+    }
+    fn add_file(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        if contents.len() > u32::MAX as usize || self.data.len() > u32::MAX as usize {
+            return Err(format!("'{}' is too large for a zip archive", name));
+        }
+        let crc: u32 = crc32(contents);
+        let size: u32 = contents.len() as u32;
+        let offset: u32 = self.data.len() as u32;
+        // Fields shared by the local header and the central directory entry:
+        // version needed, flags, method (stored), time, date (1980-01-01), crc, sizes, name length
+        let mut common: Vec<u8> = Vec::with_capacity(26);
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&33u16.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        // Local file header
+        self.data.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        self.data.extend_from_slice(&common);
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(contents);
+        // Central directory entry
+        self.central_directory
+            .extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.central_directory
+            .extend_from_slice(&20u16.to_le_bytes());
+        self.central_directory.extend_from_slice(&common);
+        // extra length, comment length, disk, internal and external attributes
+        self.central_directory.extend_from_slice(&[0; 12]);
+        self.central_directory
+            .extend_from_slice(&offset.to_le_bytes());
+        self.central_directory.extend_from_slice(name.as_bytes());
+        self.num_files += 1;
+        Ok(())
+    }
+    fn write(mut self, filename: &str) -> Result<(), String> {
+        let directory_offset: u32 = self.data.len() as u32;
+        let directory_size: u32 = self.central_directory.len() as u32;
+        self.data.extend_from_slice(&self.central_directory);
+        // End of central directory record
+        self.data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]);
+        self.data.extend_from_slice(&self.num_files.to_le_bytes());
+        self.data.extend_from_slice(&self.num_files.to_le_bytes());
+        self.data.extend_from_slice(&directory_size.to_le_bytes());
+        self.data.extend_from_slice(&directory_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        match fs::write(filename, &self.data) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+        }
+    }
+}
+
+// CRC-32 (IEEE) as used by zip
+const CRC_TABLE: [u32; 256] = crc_table();
+const fn crc_table() -> [u32; 256] {
+    let mut table: [u32; 256] = [0; 256];
+    let mut i: usize = 0;
+    while i < 256 {
+        let mut crc: u32 = i as u32;
+        let mut bit: usize = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for byte in data {
+        crc = CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
@@ -0,0 +1,99 @@
+use serde::Deserialize;
+
+use super::Vec2D;
+
+// Which bodies are removed after each step, set by "removal" in the input JSON.
+// Removal is off by default.
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct RemovalPolicy {
+    // Remove bodies further than this from the centre of mass
+    #[serde(default)]
+    pub max_distance: Option<f64>,
+    // Remove bodies that are moving away from the centre of mass with positive energy
+    #[serde(default)]
+    pub remove_unbound: bool,
+}
+impl RemovalPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_distance.is_some() || self.remove_unbound
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemovalReason {
+    Distance,
+    Unbound,
+}
+impl RemovalReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RemovalReason::Distance => "distance",
+            RemovalReason::Unbound => "unbound",
+        }
+    }
+}
+
+// Finds the indexes of the bodies to remove, in order, with the reason for each.
+//
+// A body's energy treats every other body as a point mass at the centre of
+// mass, which is accurate once the body is well outside the system. Only
+// receding bodies count as unbound, so a fast body passing through the
+// centre is not removed.
+pub fn find_removals(
+    positions: &Vec2D,
+    velocities: &Vec2D,
+    gravity: f64,
+    policy: &RemovalPolicy,
+) -> Vec<(usize, RemovalReason)> {
+    let mut removals: Vec<(usize, RemovalReason)> = Vec::new();
+    let num_bodies: usize = positions.x.len();
+    if num_bodies == 0 || !policy.is_enabled() {
+        return removals;
+    }
+    // Centre of mass position and velocity (all masses are 1)
+    let mut com: (f64, f64, f64, f64) = (0.0, 0.0, 0.0, 0.0);
+    for i in 0..num_bodies {
+        com.0 += positions.x[i];
+        com.1 += positions.y[i];
+        com.2 += velocities.x[i];
+        com.3 += velocities.y[i];
+    }
+    com.0 /= num_bodies as f64;
+    com.1 /= num_bodies as f64;
+    com.2 /= num_bodies as f64;
+    com.3 /= num_bodies as f64;
+    let other_mass: f64 = (num_bodies - 1) as f64;
+    for i in 0..num_bodies {
+        let dx: f64 = positions.x[i] - com.0;
+        let dy: f64 = positions.y[i] - com.1;
+        let distance: f64 = (dx * dx + dy * dy).sqrt();
+        if policy
+            .max_distance
+            .is_some_and(|max_distance| distance > max_distance)
+        {
+            removals.push((i, RemovalReason::Distance));
+            continue;
+        }
+        if policy.remove_unbound && distance > 0.0 {
+            let dvx: f64 = velocities.x[i] - com.2;
+            let dvy: f64 = velocities.y[i] - com.3;
+            let receding: bool = dx * dvx + dy * dvy > 0.0;
+            let energy: f64 = 0.5 * (dvx * dvx + dvy * dvy) - gravity * other_mass / distance;
+            if receding && energy > 0.0 {
+                removals.push((i, RemovalReason::Unbound));
+            }
+        }
+    }
+    removals
+}
+
+// Removes the values at the given (ordered) indexes, keeping the rest in order
+pub fn remove_indexes<T>(values: &mut Vec<T>, removals: &[(usize, RemovalReason)]) {
+    let mut removals = removals.iter().peekable();
+    let mut index: usize = 0;
+    values.retain(|_| {
+        let keep: bool = removals.next_if(|(removed, _)| *removed == index).is_none();
+        index += 1;
+        keep
+    });
+}
@@ -0,0 +1,370 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+use crate::input::{self, parse_value};
+use crate::physics::{Point, Vec2D};
+use crate::replay::PositionLogReader;
+
+use density::{ColourMap, DensityMap, Scaling};
+
+use self::animation::GifWriter;
+
+pub mod animation;
+
+// The same palette the web build draws bodies with
+pub const STAR_COLOURS_LEN: usize = 15;
+pub const STAR_COLOURS: [[u8; 3]; STAR_COLOURS_LEN] = [
+    [155, 176, 255],
+    [170, 191, 255],
+    [202, 215, 255],
+    [248, 247, 255],
+    [248, 247, 255],
+    [248, 247, 255],
+    [255, 255, 255],
+    [248, 247, 255],
+    [255, 255, 255],
+    [255, 255, 255],
+    [255, 255, 255],
+    [255, 244, 234],
+    [255, 244, 234],
+    [255, 210, 161],
+    [255, 204, 111],
+];
+const BACKGROUND_COLOUR: [u8; 3] = [0, 0, 0];
+const BODY_DRAW_SIZE: usize = 2;
+// The fraction of bodies the auto camera keeps inside the frame
+const CAMERA_PERCENTILE: f64 = 0.98;
+
+const DEFAULT_WIDTH: usize = 800;
+const DEFAULT_HEIGHT: usize = 800;
+const DEFAULT_GIF_FPS: f64 = 25.0;
+
+// Decides which region of the simulation is shown in a frame
+pub enum Camera {
+    // Follows the centre of mass and scales so the percentile body is at the frame edge
+    Auto { scale_multiplier: f64 },
+    // Always shows the given width of the simulation around the given centre
+    Fixed { centre: Point, width: f64 },
+}
+impl Camera {
+    // Returns the simulation position at the centre of the frame and the pixels per unit length
+    pub fn view(&self, positions: &Vec2D, image_width: usize) -> (Point, f64) {
+        match self {
+            Camera::Auto { scale_multiplier } => {
+                // Removed bodies are NaN and left out
+                let present: Vec<usize> = (0..positions.x.len())
+                    .filter(|&i| !positions.x[i].is_nan())
+                    .collect();
+                let num_bodies: usize = present.len();
+                if num_bodies == 0 {
+                    return (Point::new(0.0, 0.0), 1.0);
+                }
+                let mut com: Point = Point::new(0.0, 0.0);
+                for &i in &present {
+                    com.x += positions.x[i];
+                    com.y += positions.y[i];
+                }
+                com.x /= num_bodies as f64;
+                com.y /= num_bodies as f64;
+                let mut distances: Vec<f64> = Vec::with_capacity(num_bodies);
+                for &i in &present {
+                    let dx: f64 = positions.x[i] - com.x;
+                    let dy: f64 = positions.y[i] - com.y;
+                    distances.push((dx * dx + dy * dy).sqrt());
+                }
+                distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let percentile: f64 = distances[(CAMERA_PERCENTILE * num_bodies as f64) as usize];
+                let scale: f64 = scale_multiplier * image_width as f64 / (2.0 * percentile);
+                (com, scale)
+            }
+            Camera::Fixed { centre, width } => {
+                (Point::new(centre.x, centre.y), image_width as f64 / width)
+            }
+        }
+    }
+}
+
+// An RGBA image that frames are drawn into
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+        }
+    }
+    pub fn clear(&mut self, colour: [u8; 3]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel[..3].copy_from_slice(&colour);
+            pixel[3] = 255;
+        }
+    }
+    // Fills a size by size square with its top left corner at (x, y), clipped to the image
+    pub fn fill_square(&mut self, x: usize, y: usize, size: usize, colour: [u8; 3]) {
+        for row in y..(y + size).min(self.height) {
+            for column in x..(x + size).min(self.width) {
+                let index: usize = (row * self.width + column) * 4;
+                self.pixels[index..index + 3].copy_from_slice(&colour);
+                self.pixels[index + 3] = 255;
+            }
+        }
+    }
+    pub fn save_png(&self, filename: &str) -> Result<(), String> {
+        let file: File = match File::create(filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+        };
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = match encoder.write_header() {
+            Ok(writer) => writer,
+            Err(err) => return Err(format!("Error writing '{}': {}", filename, err)),
+        };
+        match writer.write_image_data(&self.pixels) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+        }
+    }
+}
+
+// Draws one dot per body, coloured by id the same way as the web build.
+// Bodies without ids are identified by their index.
+pub fn draw_bodies(image: &mut Image, positions: &Vec2D, ids: Option<&[u64]>, camera: &Camera) {
+    image.clear(BACKGROUND_COLOUR);
+    let (centre, scale) = camera.view(positions, image.width);
+    let half_width: f64 = image.width as f64 / 2.0;
+    let half_height: f64 = image.height as f64 / 2.0;
+    for i in 0..positions.x.len() {
+        let canvas_x: f64 = (positions.x[i] - centre.x) * scale + half_width;
+        let canvas_y: f64 = (positions.y[i] - centre.y) * scale + half_height;
+        // if inside the image, draw the body
+        if canvas_x >= 0.0
+            && canvas_x < image.width as f64
+            && canvas_y >= 0.0
+            && canvas_y < image.height as f64
+        {
+            let id: u64 = ids.map_or(i as u64, |ids| ids[i]);
+            let colour: [u8; 3] = STAR_COLOURS[(id % STAR_COLOURS_LEN as u64) as usize];
+            image.fill_square(canvas_x as usize, canvas_y as usize, BODY_DRAW_SIZE, colour);
+        }
+    }
+}
+
+// Accumulates the bodies into a density map and colours it into the image
+pub fn draw_density(
+    image: &mut Image,
+    positions: &Vec2D,
+    camera: &Camera,
+    density_map: &mut DensityMap,
+    options: &DensityOptions,
+) {
+    let (centre, scale) = camera.view(positions, image.width);
+    density_map.accumulate(&positions.x, &positions.y, (centre.x, centre.y), scale);
+    density_map.smooth(options.smoothing);
+    density_map.to_rgba(options.scaling, options.colour_map, &mut image.pixels);
+}
+
+// Settings for drawing frames as density maps instead of one dot per body
+pub struct DensityOptions {
+    pub colour_map: ColourMap,
+    pub scaling: Scaling,
+    // Standard deviation of the smoothing kernel in pixels, 0 for none
+    pub smoothing: f64,
+}
+impl DensityOptions {
+    pub fn new() -> DensityOptions {
+        DensityOptions {
+            colour_map: ColourMap::Inferno,
+            scaling: Scaling::Log,
+            smoothing: 0.0,
+        }
+    }
+}
+impl Default for DensityOptions {
+    fn default() -> Self {
+        DensityOptions::new()
+    }
+}
+
+// Options for the render subcommand
+pub struct RenderOptions {
+    pub width: usize,
+    pub height: usize,
+    pub camera: Camera,
+    pub out_dir: Option<String>,
+    pub density: Option<DensityOptions>,
+    // Render every stride-th frame of the position log
+    pub stride: usize,
+    // Encode the frames into this animated GIF instead of writing PNGs
+    pub gif: Option<String>,
+    pub fps: f64,
+}
+impl RenderOptions {
+    // Parses the arguments following the simulation name:
+    // [--size <width> <height>] [--zoom <multiplier>] [--fixed <centre x> <centre y> <width>] [--out <directory>]
+    // [--density] [--colour-map <grey|inferno|viridis|stars>] [--scaling <linear|log|asinh>] [--smoothing <pixels>]
+    // [--stride <frames>] [--gif <file>] [--fps <frames per second>]
+    pub fn parse(args: &[String]) -> Result<RenderOptions, String> {
+        let mut options: RenderOptions = RenderOptions {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            camera: Camera::Auto {
+                scale_multiplier: 1.0,
+            },
+            out_dir: None,
+            density: None,
+            stride: 1,
+            gif: None,
+            fps: DEFAULT_GIF_FPS,
+        };
+        let mut i: usize = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--size" => {
+                    options.width = parse_value(args, i + 1)?;
+                    options.height = parse_value(args, i + 2)?;
+                    i += 3;
+                }
+                "--zoom" => {
+                    options.camera = Camera::Auto {
+                        scale_multiplier: parse_value(args, i + 1)?,
+                    };
+                    i += 2;
+                }
+                "--fixed" => {
+                    options.camera = Camera::Fixed {
+                        centre: Point::new(parse_value(args, i + 1)?, parse_value(args, i + 2)?),
+                        width: parse_value(args, i + 3)?,
+                    };
+                    i += 4;
+                }
+                "--out" => {
+                    options.out_dir = Some(parse_value(args, i + 1)?);
+                    i += 2;
+                }
+                "--density" => {
+                    options.density.get_or_insert_with(DensityOptions::new);
+                    i += 1;
+                }
+                "--colour-map" => {
+                    let name: String = parse_value(args, i + 1)?;
+                    options
+                        .density
+                        .get_or_insert_with(DensityOptions::new)
+                        .colour_map = match ColourMap::from_name(&name) {
+                        Some(colour_map) => colour_map,
+                        None => return Err(format!("Unknown colour map '{}'", name)),
+                    };
+                    i += 2;
+                }
+                "--scaling" => {
+                    let name: String = parse_value(args, i + 1)?;
+                    options
+                        .density
+                        .get_or_insert_with(DensityOptions::new)
+                        .scaling = match Scaling::from_name(&name) {
+                        Some(scaling) => scaling,
+                        None => return Err(format!("Unknown scaling '{}'", name)),
+                    };
+                    i += 2;
+                }
+                "--smoothing" => {
+                    options
+                        .density
+                        .get_or_insert_with(DensityOptions::new)
+                        .smoothing = parse_value(args, i + 1)?;
+                    i += 2;
+                }
+                "--stride" => {
+                    options.stride = parse_value(args, i + 1)?;
+                    i += 2;
+                }
+                "--gif" => {
+                    options.gif = Some(parse_value(args, i + 1)?);
+                    i += 2;
+                }
+                "--fps" => {
+                    options.fps = parse_value(args, i + 1)?;
+                    i += 2;
+                }
+                other => return Err(format!("Unknown render option '{}'", other)),
+            }
+        }
+        if options.width == 0 || options.height == 0 {
+            return Err(String::from("Frame size must be at least 1x1"));
+        }
+        if options.stride == 0 {
+            return Err(String::from("Frame stride must be at least 1"));
+        }
+        Ok(options)
+    }
+}
+
+// Renders every stride-th frame of the position log to a PNG in the output
+// directory, or into a single animated GIF
+pub fn render(sim_name: &str, options: &RenderOptions) -> Result<(), String> {
+    let constants: input::Constants = input::parse_input(&format!("{}.json", sim_name))?;
+    let mut reader: PositionLogReader = PositionLogReader::open(sim_name, &constants)?;
+    let out_dir: String = match &options.out_dir {
+        Some(out_dir) => out_dir.clone(),
+        None => format!("{}_frames", sim_name),
+    };
+    let mut gif_writer: Option<GifWriter> = match &options.gif {
+        Some(filename) => Some(GifWriter::create(
+            filename,
+            options.width,
+            options.height,
+            options.fps,
+        )?),
+        None => {
+            if let Err(err) = fs::create_dir_all(&out_dir) {
+                return Err(format!("Error creating '{}': {}", out_dir, err));
+            }
+            None
+        }
+    };
+    let mut positions: Vec2D = Vec2D::new(constants.num_bodies);
+    let mut image: Image = Image::new(options.width, options.height);
+    let mut density_map: DensityMap = DensityMap::new(options.width, options.height);
+    let mut log_frame: usize = 0;
+    let mut frame: usize = 0;
+    while reader.read_frame(&mut positions)? {
+        let skip: bool = !log_frame.is_multiple_of(options.stride);
+        log_frame += 1;
+        if skip {
+            continue;
+        }
+        match &options.density {
+            Some(density) => draw_density(
+                &mut image,
+                &positions,
+                &options.camera,
+                &mut density_map,
+                density,
+            ),
+            None => draw_bodies(
+                &mut image,
+                &positions,
+                reader.ids.as_deref(),
+                &options.camera,
+            ),
+        }
+        match &mut gif_writer {
+            Some(gif_writer) => gif_writer.add_frame(&image)?,
+            None => image.save_png(&format!("{}/frame_{:05}.png", out_dir, frame))?,
+        }
+        frame += 1;
+    }
+    match &options.gif {
+        Some(filename) => println!("Rendered {} frames to {}", frame, filename),
+        None => println!("Rendered {} frames to {}", frame, out_dir),
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use super::Image;
+
+// Quantization speed passed to the GIF encoder, from 1 (best) to 30 (fastest)
+const QUANTIZATION_SPEED: i32 = 10;
+
+// Encodes rendered frames into an animated GIF that loops forever
+pub struct GifWriter {
+    encoder: gif::Encoder<BufWriter<File>>,
+    filename: String,
+    // Frame delay in hundredths of a second
+    delay: u16,
+}
+impl GifWriter {
+    pub fn create(
+        filename: &str,
+        width: usize,
+        height: usize,
+        fps: f64,
+    ) -> Result<GifWriter, String> {
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(format!("GIF frames can be at most {0}x{0}", u16::MAX));
+        }
+        if fps <= 0.0 {
+            return Err(String::from("GIF frame rate must be positive"));
+        }
+        let file: File = match File::create(filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+        };
+        let mut encoder =
+            match gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[]) {
+                Ok(encoder) => encoder,
+                Err(err) => return Err(format!("Error writing '{}': {}", filename, err)),
+            };
+        if let Err(err) = encoder.set_repeat(gif::Repeat::Infinite) {
+            return Err(format!("Error writing '{}': {}", filename, err));
+        }
+        Ok(GifWriter {
+            encoder,
+            filename: filename.to_string(),
+            delay: (100.0 / fps).round().max(1.0) as u16,
+        })
+    }
+    // Quantizes the image to a 256 colour palette and appends it as the next frame
+    pub fn add_frame(&mut self, image: &Image) -> Result<(), String> {
+        let mut pixels: Vec<u8> = image.pixels.clone();
+        let mut frame = gif::Frame::from_rgba_speed(
+            image.width as u16,
+            image.height as u16,
+            &mut pixels,
+            QUANTIZATION_SPEED,
+        );
+        frame.delay = self.delay;
+        match self.encoder.write_frame(&frame) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", self.filename, err)),
+        }
+    }
+}
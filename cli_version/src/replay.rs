@@ -0,0 +1,193 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+
+use crate::input::Constants;
+use crate::physics::logger::compressed::{CompressedLogReader, PositionLogFormat};
+use crate::physics::Vec2D;
+
+// One frame of a run. Velocities, masses and ids are only present when the log recorded them,
+// and bodies without ids are identified by their index.
+pub struct Snapshot {
+    pub time: f64,
+    pub positions: Vec2D,
+    pub velocities: Option<Vec2D>,
+    pub masses: Option<Vec<f64>>,
+    pub ids: Option<Vec<u64>>,
+}
+impl Snapshot {
+    pub fn new(num_bodies: usize) -> Snapshot {
+        Snapshot {
+            time: 0.0,
+            positions: Vec2D::new(num_bodies),
+            velocities: None,
+            masses: None,
+            ids: None,
+        }
+    }
+    pub fn num_bodies(&self) -> usize {
+        self.positions.x.len()
+    }
+    // The id of the body at index i
+    pub fn id(&self, i: usize) -> u64 {
+        match &self.ids {
+            Some(ids) => ids[i],
+            None => i as u64,
+        }
+    }
+    // A copy without the bodies removed during the run, whose positions are NaN.
+    // The remaining bodies keep their ids.
+    pub fn without_removed(&self) -> Snapshot {
+        let present: Vec<usize> = (0..self.num_bodies())
+            .filter(|&i| !self.positions.x[i].is_nan())
+            .collect();
+        let select = |values: &Vec2D| Vec2D {
+            x: present.iter().map(|&i| values.x[i]).collect(),
+            y: present.iter().map(|&i| values.y[i]).collect(),
+        };
+        Snapshot {
+            time: self.time,
+            positions: select(&self.positions),
+            velocities: self.velocities.as_ref().map(select),
+            masses: self
+                .masses
+                .as_ref()
+                .map(|masses| present.iter().map(|&i| masses[i]).collect()),
+            ids: Some(present.iter().map(|&i| self.id(i)).collect()),
+        }
+    }
+}
+
+// Reads the frames of a simulation's position log in whichever format the
+// input JSON says it was written in
+pub struct PositionLogReader {
+    source: LogSource,
+    pub num_frames: usize,
+    // The id of the body in each frame slot, if the run recorded them
+    pub ids: Option<Vec<u64>>,
+}
+enum LogSource {
+    Raw(RawLogReader),
+    Compressed(CompressedLogReader),
+}
+impl PositionLogReader {
+    // Opens <sim name>.bin, or <sim name>.binz for compressed logs, and
+    // <sim name>_ids.bin if the run wrote one
+    pub fn open(sim_name: &str, constants: &Constants) -> Result<PositionLogReader, String> {
+        let source: LogSource = match constants.position_log {
+            PositionLogFormat::Raw => LogSource::Raw(RawLogReader::open(
+                &format!("{}.bin", sim_name),
+                constants.num_bodies,
+            )?),
+            _ => LogSource::Compressed(CompressedLogReader::open(
+                &format!("{}.binz", sim_name),
+                constants.num_bodies,
+            )?),
+        };
+        let num_frames: usize = match &source {
+            LogSource::Raw(reader) => reader.num_frames,
+            LogSource::Compressed(reader) => reader.num_frames,
+        };
+        let ids: Option<Vec<u64>> =
+            read_ids(&format!("{}_ids.bin", sim_name), constants.num_bodies)?;
+        Ok(PositionLogReader {
+            source,
+            num_frames,
+            ids,
+        })
+    }
+    // Reads the next frame into positions, returning false once all frames have been read
+    pub fn read_frame(&mut self, positions: &mut Vec2D) -> Result<bool, String> {
+        match &mut self.source {
+            LogSource::Raw(reader) => reader.read_frame(positions),
+            LogSource::Compressed(reader) => reader.read_frame(positions),
+        }
+    }
+    // The largest position error along each axis in the last frame read,
+    // which is only non-zero for quantized logs
+    pub fn max_error(&self) -> (f64, f64) {
+        match &self.source {
+            LogSource::Raw(_) => (0.0, 0.0),
+            LogSource::Compressed(reader) => reader.max_error(),
+        }
+    }
+}
+
+// Reads the ids written by log_ids, or None if there is no id file
+fn read_ids(filename: &str, num_bodies: usize) -> Result<Option<Vec<u64>>, String> {
+    let data: Vec<u8> = match fs::read(filename) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("Error reading '{}': {}", filename, err)),
+    };
+    if data.len() != num_bodies * 8 {
+        return Err(format!(
+            "'{}' is {} bytes but should hold {} ids",
+            filename,
+            data.len(),
+            num_bodies
+        ));
+    }
+    Ok(Some(
+        data.chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect(),
+    ))
+}
+
+// Reads the frames of a position log written by log_positions.
+//
+// Each frame is num_bodies little-endian f64 x positions followed by
+// num_bodies little-endian f64 y positions.
+struct RawLogReader {
+    reader: BufReader<File>,
+    num_bodies: usize,
+    num_frames: usize,
+    frames_read: usize,
+    buffer: Vec<u8>,
+}
+impl RawLogReader {
+    fn open(filename: &str, num_bodies: usize) -> Result<RawLogReader, String> {
+        let file: File = match File::open(filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error opening '{}': {}", filename, err)),
+        };
+        let file_size: u64 = match file.metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(err) => return Err(format!("Error reading '{}': {}", filename, err)),
+        };
+        let frame_size: u64 = (num_bodies * 2 * 8) as u64;
+        if frame_size == 0 || !file_size.is_multiple_of(frame_size) {
+            return Err(format!(
+                "'{}' is {} bytes which is not a whole number of {} body frames",
+                filename, file_size, num_bodies
+            ));
+        }
+        Ok(RawLogReader {
+            reader: BufReader::new(file),
+            num_bodies,
+            num_frames: (file_size / frame_size) as usize,
+            frames_read: 0,
+            buffer: vec![0; num_bodies * 8],
+        })
+    }
+    fn read_frame(&mut self, positions: &mut Vec2D) -> Result<bool, String> {
+        if self.frames_read == self.num_frames {
+            return Ok(false);
+        }
+        self.read_axis(&mut positions.x)?;
+        self.read_axis(&mut positions.y)?;
+        self.frames_read += 1;
+        Ok(true)
+    }
+    fn read_axis(&mut self, axis: &mut Vec<f64>) -> Result<(), String> {
+        if let Err(err) = self.reader.read_exact(&mut self.buffer) {
+            return Err(format!("Error reading position log: {}", err));
+        }
+        axis.clear();
+        for bytes in self.buffer.chunks_exact(8) {
+            axis.push(f64::from_le_bytes(bytes.try_into().unwrap()));
+        }
+        debug_assert_eq!(axis.len(), self.num_bodies);
+        Ok(())
+    }
+}
@@ -0,0 +1,382 @@
+use std::mem;
+
+use crate::input::{initialize_bodies, load_initial_conditions, Constants};
+use crate::physics::bh_tree::Tree;
+use crate::physics::logger::background::{BackgroundWriter, BodyState};
+use crate::physics::logger::{calc_energy, Energy};
+use crate::physics::removal::{self, RemovalReason};
+use crate::physics::{self, Integrator, Solver, Vec2D};
+use crate::timing::{Phase, Timer, TimingReport};
+
+// Fraction of a step that run_until's end time may fall short of a step
+// boundary, so rounding in end_time / delta_t doesn't add an extra step
+const END_TIME_TOLERANCE: f64 = 1e-9;
+
+// Returned by step callbacks to keep running or stop run_until early
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+pub type StepCallback = Box<dyn FnMut(&Simulation) -> Control>;
+
+// Configures and creates a Simulation.
+//
+// Bodies default to the constants' initial conditions, or random positions
+// if there are none. Nothing is written to disk unless an output is set.
+pub struct SimulationBuilder {
+    constants: Constants,
+    bodies: Option<(Vec2D, Vec2D)>,
+    ids: Option<Vec<u64>>,
+    solver: Solver,
+    integrator: Integrator,
+    output: Option<String>,
+}
+impl SimulationBuilder {
+    pub fn new(constants: Constants) -> SimulationBuilder {
+        SimulationBuilder {
+            constants,
+            bodies: None,
+            ids: None,
+            solver: Solver::default(),
+            integrator: Integrator::default(),
+            output: None,
+        }
+    }
+    // Starts from the given positions and velocities, replacing num_bodies in the constants
+    pub fn bodies(mut self, positions: Vec2D, velocities: Vec2D) -> SimulationBuilder {
+        self.bodies = Some((positions, velocities));
+        self
+    }
+    // Identifies the bodies by the given ids rather than by their starting index
+    pub fn ids(mut self, ids: Vec<u64>) -> SimulationBuilder {
+        self.ids = Some(ids);
+        self
+    }
+    pub fn solver(mut self, solver: Solver) -> SimulationBuilder {
+        self.solver = solver;
+        self
+    }
+    pub fn integrator(mut self, integrator: Integrator) -> SimulationBuilder {
+        self.integrator = integrator;
+        self
+    }
+    // Writes the position log, energy log and NumPy output configured in the
+    // constants to files named after sim_name, as the CLI does
+    pub fn output(mut self, sim_name: &str) -> SimulationBuilder {
+        self.output = Some(sim_name.to_string());
+        self
+    }
+    pub fn build(self) -> Result<Simulation, String> {
+        let mut constants: Constants = self.constants;
+        let mut ids: Vec<u64> = Vec::new();
+        let (positions, velocities): (Vec2D, Vec2D) = match self.bodies {
+            Some((positions, velocities)) => {
+                let num_bodies: usize = positions.x.len();
+                if positions.y.len() != num_bodies
+                    || velocities.x.len() != num_bodies
+                    || velocities.y.len() != num_bodies
+                {
+                    return Err(String::from(
+                        "Positions and velocities must all have one value per body",
+                    ));
+                }
+                constants.num_bodies = num_bodies;
+                (positions, velocities)
+            }
+            None => {
+                let mut positions: Vec2D = Vec2D::new(constants.num_bodies);
+                let mut velocities: Vec2D = Vec2D::new(constants.num_bodies);
+                match &constants.initial_conditions {
+                    Some(initial_conditions) => load_initial_conditions(
+                        initial_conditions,
+                        &mut positions,
+                        &mut velocities,
+                        &mut ids,
+                        constants.num_bodies,
+                    )?,
+                    None => {
+                        initialize_bodies(&mut positions, &mut velocities, constants.num_bodies)
+                    }
+                }
+                (positions, velocities)
+            }
+        };
+        if let Some(given_ids) = self.ids {
+            ids = given_ids;
+        }
+        if ids.is_empty() {
+            ids = (0..constants.num_bodies as u64).collect();
+        }
+        if ids.len() != constants.num_bodies {
+            return Err(format!(
+                "{} ids were given for {} bodies",
+                ids.len(),
+                constants.num_bodies
+            ));
+        }
+        let tracked: Vec<usize> = find_tracked(&ids, &constants.tracked_ids);
+        if tracked.len() != constants.tracked_ids.len() {
+            for tracked_id in &constants.tracked_ids {
+                if !ids.contains(tracked_id) {
+                    return Err(format!("There is no body with tracked id {}", tracked_id));
+                }
+            }
+        }
+        if constants.write_interval == 0 {
+            return Err(String::from("write_interval must be at least 1"));
+        }
+        let writer: Option<BackgroundWriter> = match &self.output {
+            Some(sim_name) => Some(BackgroundWriter::spawn(sim_name, &constants, &ids)?),
+            None => None,
+        };
+        let mut simulation: Simulation = Simulation {
+            accelerations: Vec2D::new(constants.num_bodies),
+            bh_tree: Tree::new(constants.num_bodies),
+            positions,
+            velocities,
+            slots: (0..ids.len()).collect(),
+            ids,
+            tracked,
+            num_removed: 0,
+            progress_steps: constants.num_steps,
+            constants,
+            solver: self.solver,
+            integrator: self.integrator,
+            writer,
+            callbacks: Vec::new(),
+            step_count: 0,
+            timer: Timer::new(),
+        };
+        // Leapfrog starts each step with the acceleration from the end of the last
+        if simulation.integrator == Integrator::Leapfrog {
+            simulation.calc_accelerations();
+        }
+        Ok(simulation)
+    }
+}
+
+// A running simulation. Each step logs any configured output for the current
+// state, advances the bodies by delta_t and then calls the step callbacks.
+pub struct Simulation {
+    positions: Vec2D,
+    velocities: Vec2D,
+    // Identifies each body for its whole life, whatever its index
+    ids: Vec<u64>,
+    // The slot of each body in output frames, which is its index at the start of the run
+    slots: Vec<usize>,
+    // Indexes of the bodies in constants.tracked_ids that have not been removed
+    tracked: Vec<usize>,
+    num_removed: usize,
+    accelerations: Vec2D,
+    bh_tree: Tree,
+    constants: Constants,
+    solver: Solver,
+    integrator: Integrator,
+    writer: Option<BackgroundWriter>,
+    callbacks: Vec<StepCallback>,
+    step_count: usize,
+    // The step count progress is reported towards, num_steps or run_until's last step
+    progress_steps: usize,
+    // Phase timings of every step so far
+    timer: Timer,
+}
+impl Simulation {
+    pub fn positions(&self) -> &Vec2D {
+        &self.positions
+    }
+    pub fn velocities(&self) -> &Vec2D {
+        &self.velocities
+    }
+    pub fn ids(&self) -> &[u64] {
+        &self.ids
+    }
+    pub fn constants(&self) -> &Constants {
+        &self.constants
+    }
+    pub fn num_bodies(&self) -> usize {
+        self.constants.num_bodies
+    }
+    // The number of bodies removed by the removal policy so far
+    pub fn num_removed(&self) -> usize {
+        self.num_removed
+    }
+    pub fn step_count(&self) -> usize {
+        self.step_count
+    }
+    pub fn time(&self) -> f64 {
+        self.step_count as f64 * self.constants.delta_t
+    }
+    // The time spent in each phase of the steps so far
+    pub fn timing_report(&self) -> TimingReport {
+        self.timer.report(self.num_bodies())
+    }
+    pub fn energy(&self) -> Energy {
+        calc_energy(&self.positions, &self.velocities, &self.constants)
+    }
+    // Adds a callback run after every step. Returning Control::Stop ends run_until.
+    pub fn on_step(&mut self, callback: impl FnMut(&Simulation) -> Control + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+    fn calc_accelerations(&mut self) {
+        physics::calc_accelerations(
+            &self.positions,
+            &mut self.accelerations,
+            &mut self.bh_tree,
+            &self.constants,
+            self.solver,
+            &mut self.timer,
+        );
+    }
+    // Advances the simulation by one step, returning Control::Stop if a callback asked to stop
+    pub fn step(&mut self) -> Result<Control, String> {
+        if let Some(writer) = &mut self.writer {
+            // Log the energy
+            if self.constants.log_energy_conservation {
+                self.timer.start(Phase::Diagnostics);
+                writer.log_energy(calc_energy(
+                    &self.positions,
+                    &self.velocities,
+                    &self.constants,
+                ))?;
+            }
+            // Log the positions
+            if self
+                .step_count
+                .is_multiple_of(self.constants.write_interval)
+            {
+                self.timer.start(Phase::Io);
+                writer.log_snapshot(&self.positions, &self.velocities, &self.slots)?;
+            }
+            // Log the tracked bodies every step
+            if !self.tracked.is_empty() {
+                self.timer.start(Phase::Io);
+                let bodies: Vec<BodyState> = self
+                    .tracked
+                    .iter()
+                    .map(|&i| BodyState::new(i, &self.positions, &self.velocities, &self.ids))
+                    .collect();
+                writer.log_trajectory(
+                    self.step_count,
+                    self.step_count as f64 * self.constants.delta_t,
+                    bodies,
+                )?;
+            }
+        }
+        let delta_t: f64 = self.constants.delta_t;
+        match self.integrator {
+            Integrator::Euler => {
+                self.calc_accelerations();
+                self.timer.start(Phase::Integration);
+                physics::kick(&mut self.velocities, &self.accelerations, delta_t);
+                physics::drift(&mut self.positions, &self.velocities, delta_t);
+            }
+            Integrator::Leapfrog => {
+                self.timer.start(Phase::Integration);
+                physics::kick(&mut self.velocities, &self.accelerations, delta_t / 2.0);
+                physics::drift(&mut self.positions, &self.velocities, delta_t);
+                self.calc_accelerations();
+                self.timer.start(Phase::Integration);
+                physics::kick(&mut self.velocities, &self.accelerations, delta_t / 2.0);
+            }
+        }
+        self.timer.stop();
+        self.step_count += 1;
+        if self.constants.removal.is_enabled() {
+            self.remove_bodies()?;
+        }
+        self.timer.end_step(self.progress_steps);
+        // Callbacks borrow the simulation, so take them out while they run
+        let mut callbacks: Vec<StepCallback> = mem::take(&mut self.callbacks);
+        let mut control: Control = Control::Continue;
+        for callback in callbacks.iter_mut() {
+            if callback(self) == Control::Stop {
+                control = Control::Stop;
+            }
+        }
+        self.callbacks = callbacks;
+        Ok(control)
+    }
+    // Removes the bodies selected by the removal policy from every per-body array
+    fn remove_bodies(&mut self) -> Result<(), String> {
+        self.timer.start(Phase::Diagnostics);
+        let removals: Vec<(usize, RemovalReason)> = removal::find_removals(
+            &self.positions,
+            &self.velocities,
+            self.constants.gravity,
+            &self.constants.removal,
+        );
+        self.timer.stop();
+        if removals.is_empty() {
+            return Ok(());
+        }
+        if let Some(writer) = &mut self.writer {
+            self.timer.start(Phase::Io);
+            let bodies: Vec<(BodyState, RemovalReason)> = removals
+                .iter()
+                .map(|&(i, reason)| {
+                    (
+                        BodyState::new(i, &self.positions, &self.velocities, &self.ids),
+                        reason,
+                    )
+                })
+                .collect();
+            writer.log_removed(
+                self.step_count,
+                self.step_count as f64 * self.constants.delta_t,
+                bodies,
+            )?;
+            self.timer.stop();
+        }
+        for values in [
+            &mut self.positions,
+            &mut self.velocities,
+            &mut self.accelerations,
+        ] {
+            removal::remove_indexes(&mut values.x, &removals);
+            removal::remove_indexes(&mut values.y, &removals);
+        }
+        removal::remove_indexes(&mut self.ids, &removals);
+        removal::remove_indexes(&mut self.slots, &removals);
+        self.constants.num_bodies = self.ids.len();
+        self.num_removed += removals.len();
+        self.tracked = find_tracked(&self.ids, &self.constants.tracked_ids);
+        Ok(())
+    }
+    // Steps until the simulation time reaches end_time or a callback asks to stop
+    pub fn run_until(&mut self, end_time: f64) -> Result<(), String> {
+        if self.constants.delta_t <= 0.0 {
+            return Err(String::from("run_until needs a positive delta_t"));
+        }
+        let end_step: f64 = end_time / self.constants.delta_t - END_TIME_TOLERANCE;
+        self.progress_steps = end_step.ceil().max(0.0) as usize;
+        while (self.step_count as f64) < end_step {
+            if self.step()? == Control::Stop {
+                break;
+            }
+        }
+        Ok(())
+    }
+    // Waits for all output to be written. Must be called once the run is over
+    // to see any write errors, which are otherwise only reported by step.
+    pub fn finish(&mut self) -> Result<(), String> {
+        match self.writer.take() {
+            Some(mut writer) => {
+                self.timer.start(Phase::Io);
+                let result: Result<(), String> = writer.finish();
+                self.timer.stop();
+                result
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// Finds the indexes of the bodies with the given ids, skipping ids that are not present
+fn find_tracked(ids: &[u64], tracked_ids: &[u64]) -> Vec<usize> {
+    tracked_ids
+        .iter()
+        .filter_map(|tracked_id| ids.iter().position(|id| id == tracked_id))
+        .collect()
+}
@@ -0,0 +1,155 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+// How often the progress line is printed
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+// The phases of a simulation run that are timed separately
+#[derive(Clone, Copy)]
+pub enum Phase {
+    TreeBuild,
+    ForceWalk,
+    Integration,
+    Io,
+    Diagnostics,
+}
+const NUM_PHASES: usize = 5;
+const PHASE_NAMES: [&str; NUM_PHASES] = [
+    "tree_build",
+    "force_walk",
+    "integration",
+    "io",
+    "diagnostics",
+];
+
+// Accumulates the time spent in each phase over the whole run
+pub struct Timer {
+    phase_totals: [Duration; NUM_PHASES],
+    phase_start: Option<(Phase, Instant)>,
+    run_start: Instant,
+    last_progress: Instant,
+    steps_completed: usize,
+}
+impl Timer {
+    pub fn new() -> Timer {
+        let now: Instant = Instant::now();
+        Timer {
+            phase_totals: [Duration::ZERO; NUM_PHASES],
+            phase_start: None,
+            run_start: now,
+            last_progress: now,
+            steps_completed: 0,
+        }
+    }
+    // Starts timing the given phase, stopping the previous phase if there is one
+    pub fn start(&mut self, phase: Phase) {
+        self.stop();
+        self.phase_start = Some((phase, Instant::now()));
+    }
+    // Stops timing the current phase and adds its elapsed time to the phase total
+    pub fn stop(&mut self) {
+        if let Some((phase, start)) = self.phase_start.take() {
+            self.phase_totals[phase as usize] += start.elapsed();
+        }
+    }
+    // Records the end of a step and prints a progress line if enough time has passed
+    pub fn end_step(&mut self, num_steps: usize) {
+        self.stop();
+        self.steps_completed += 1;
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL || self.steps_completed == num_steps {
+            self.last_progress = Instant::now();
+            self.print_progress(num_steps);
+        }
+    }
+    // Prints the current step, the step rate and the estimated time remaining
+    fn print_progress(&self, num_steps: usize) {
+        let elapsed: f64 = self.run_start.elapsed().as_secs_f64();
+        let steps_per_second: f64 = self.steps_completed as f64 / elapsed;
+        let remaining_steps: usize = num_steps.saturating_sub(self.steps_completed);
+        let eta: f64 = remaining_steps as f64 / steps_per_second;
+        println!(
+            "Step {}/{} ({:.1}%) | {:.2} steps/s | ETA {:.1} s",
+            self.steps_completed,
+            num_steps,
+            100.0 * self.steps_completed as f64 / num_steps as f64,
+            steps_per_second,
+            eta
+        );
+    }
+    // Builds the timing report for the run so far
+    pub fn report(&self, num_bodies: usize) -> TimingReport {
+        let total: Duration = self.run_start.elapsed();
+        let mut phases: Vec<PhaseReport> = Vec::with_capacity(NUM_PHASES);
+        for (i, name) in PHASE_NAMES.iter().enumerate() {
+            let total_us: f64 = self.phase_totals[i].as_secs_f64() * 1e6;
+            phases.push(PhaseReport {
+                name: name.to_string(),
+                total_us,
+                mean_us_per_step: total_us / self.steps_completed.max(1) as f64,
+                fraction_of_total: self.phase_totals[i].as_secs_f64() / total.as_secs_f64(),
+            });
+        }
+        TimingReport {
+            num_bodies,
+            num_steps: self.steps_completed,
+            total_us: total.as_secs_f64() * 1e6,
+            steps_per_second: self.steps_completed as f64 / total.as_secs_f64(),
+            phases,
+        }
+    }
+}
+impl Default for Timer {
+    fn default() -> Self {
+        Timer::new()
+    }
+}
+
+#[derive(Serialize)]
+pub struct PhaseReport {
+    pub name: String,
+    pub total_us: f64,
+    pub mean_us_per_step: f64,
+    pub fraction_of_total: f64,
+}
+
+#[derive(Serialize)]
+pub struct TimingReport {
+    pub num_bodies: usize,
+    pub num_steps: usize,
+    pub total_us: f64,
+    pub steps_per_second: f64,
+    pub phases: Vec<PhaseReport>,
+}
+impl TimingReport {
+    // Prints the total time and the per-phase breakdown to the console
+    pub fn print(&self) {
+        println!("Time: {:.0} us", self.total_us);
+        for phase in &self.phases {
+            println!(
+                "{:>12}: {:>12.0} us ({:>5.1}%) | {:.1} us/step",
+                phase.name,
+                phase.total_us,
+                100.0 * phase.fraction_of_total,
+                phase.mean_us_per_step
+            );
+        }
+    }
+    // Writes the report as JSON to the given file
+    pub fn write(&self, filename: &str) -> Result<(), String> {
+        let json: String = match serde_json::to_string_pretty(self) {
+            Ok(json) => json,
+            Err(err) => return Err(format!("Error serialising timing report: {}", err)),
+        };
+        let mut file: File = match File::create(filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error creating '{}': {}", filename, err)),
+        };
+        match file.write_all(json.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing '{}': {}", filename, err)),
+        }
+    }
+}
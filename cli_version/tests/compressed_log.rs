@@ -0,0 +1,200 @@
+// Writes compressed position logs and reads them back
+use std::fs;
+use std::path::PathBuf;
+
+use space_clicker::physics::logger::compressed::{
+    CompressedLogReader, CompressedLogger, PositionLogFormat,
+};
+use space_clicker::physics::Vec2D;
+
+// A simulation name in the temporary directory, unique to the test
+fn temp_sim_name(test: &str) -> String {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("space_clicker_{}_{}", test, std::process::id()));
+    path.to_str().unwrap().to_string()
+}
+
+// Writes the frames to a log with the given format and reads them back
+fn roundtrip(test: &str, format: PositionLogFormat, frames: &[Vec2D]) -> Vec<Vec2D> {
+    let sim_name: String = temp_sim_name(test);
+    let num_bodies: usize = frames[0].x.len();
+    let mut logger: CompressedLogger =
+        CompressedLogger::create(&sim_name, format, num_bodies).unwrap();
+    for frame in frames {
+        logger.log(frame).unwrap();
+    }
+    logger.finish().unwrap();
+    drop(logger);
+
+    let filename: String = format!("{}.binz", sim_name);
+    let mut reader: CompressedLogReader = CompressedLogReader::open(&filename, num_bodies).unwrap();
+    assert_eq!(reader.num_frames, frames.len());
+    let mut read: Vec<Vec2D> = Vec::new();
+    let mut positions: Vec2D = Vec2D {
+        x: Vec::new(),
+        y: Vec::new(),
+    };
+    while reader.read_frame(&mut positions).unwrap() {
+        read.push(positions.clone());
+    }
+    fs::remove_file(&filename).unwrap();
+    read
+}
+
+// Frames of bodies on spirals, with the last body removed after the first frame
+fn frames_with_removal() -> Vec<Vec2D> {
+    (0..3)
+        .map(|frame| {
+            let mut positions: Vec2D = Vec2D {
+                x: (0..50)
+                    .map(|i| (i as f64 * 0.37 + frame as f64).cos() * i as f64)
+                    .collect(),
+                y: (0..50)
+                    .map(|i| (i as f64 * 0.37 + frame as f64).sin() * i as f64 - 3.5)
+                    .collect(),
+            };
+            if frame > 0 {
+                positions.x[49] = f64::NAN;
+                positions.y[49] = f64::NAN;
+            }
+            positions
+        })
+        .collect()
+}
+
+#[test]
+fn lossless_roundtrip_with_removed_bodies() {
+    let frames: Vec<Vec2D> = frames_with_removal();
+    let read: Vec<Vec2D> = roundtrip("lossless_removed", PositionLogFormat::Lossless, &frames);
+    for (frame, read) in frames.iter().zip(read.iter()) {
+        for (value, read) in frame
+            .x
+            .iter()
+            .chain(&frame.y)
+            .zip(read.x.iter().chain(&read.y))
+        {
+            assert_eq!(value.to_bits(), read.to_bits());
+        }
+    }
+}
+
+#[test]
+fn quantized_roundtrip_with_removed_bodies() {
+    let frames: Vec<Vec2D> = frames_with_removal();
+    let read: Vec<Vec2D> = roundtrip(
+        "quantized_removed",
+        PositionLogFormat::Quantized(12),
+        &frames,
+    );
+    for (frame, read) in frames.iter().zip(read.iter()) {
+        for (value, read) in frame
+            .x
+            .iter()
+            .chain(&frame.y)
+            .zip(read.x.iter().chain(&read.y))
+        {
+            if value.is_nan() {
+                assert!(read.is_nan());
+            } else {
+                assert!((value - read).abs() < 0.1, "{} read as {}", value, read);
+            }
+        }
+    }
+}
+
+#[test]
+fn lossless_roundtrip_is_bit_exact() {
+    // Extreme values and large jumps between frames cover the whole range of
+    // the zigzag encoded changes
+    let extremes: Vec<f64> = vec![
+        0.0,
+        -0.0,
+        f64::MIN_POSITIVE,
+        -f64::MIN_POSITIVE / 3.0,
+        f64::MAX,
+        f64::MIN,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        1.0 / 3.0,
+        -1e-300,
+    ];
+    let mut reversed: Vec<f64> = extremes.clone();
+    reversed.reverse();
+    let frames: Vec<Vec2D> = vec![
+        Vec2D {
+            x: extremes.clone(),
+            y: reversed.clone(),
+        },
+        Vec2D {
+            x: reversed,
+            y: extremes.clone(),
+        },
+        Vec2D {
+            x: extremes.clone(),
+            y: extremes,
+        },
+    ];
+    let read: Vec<Vec2D> = roundtrip("lossless_exact", PositionLogFormat::Lossless, &frames);
+    for (frame, read) in frames.iter().zip(read.iter()) {
+        for (value, read) in frame
+            .x
+            .iter()
+            .chain(&frame.y)
+            .zip(read.x.iter().chain(&read.y))
+        {
+            assert_eq!(value.to_bits(), read.to_bits());
+        }
+    }
+}
+
+#[test]
+fn quantized_roundtrip_within_half_a_step() {
+    for bits in [1, 8, 16, 32] {
+        let sim_name: String = temp_sim_name(&format!("quantized_{}", bits));
+        let frames: Vec<Vec2D> = (0..4)
+            .map(|frame| Vec2D {
+                x: (0..100)
+                    .map(|i| ((i * 7919 + frame * 31) % 1000) as f64 * 0.173 - 50.0)
+                    .collect(),
+                // The same value for every body, which has a zero step
+                y: vec![frame as f64 * 2.5; 100],
+            })
+            .collect();
+        let mut logger: CompressedLogger =
+            CompressedLogger::create(&sim_name, PositionLogFormat::Quantized(bits), 100).unwrap();
+        for frame in &frames {
+            logger.log(frame).unwrap();
+        }
+        logger.finish().unwrap();
+        drop(logger);
+
+        let filename: String = format!("{}.binz", sim_name);
+        let mut reader: CompressedLogReader = CompressedLogReader::open(&filename, 100).unwrap();
+        let mut positions: Vec2D = Vec2D {
+            x: Vec::new(),
+            y: Vec::new(),
+        };
+        for frame in &frames {
+            assert!(reader.read_frame(&mut positions).unwrap());
+            let (max_error_x, max_error_y): (f64, f64) = reader.max_error();
+            // The step is the range of the frame over 2^bits - 1 levels
+            let range: f64 = 0.173 * 999.0;
+            let expected_step: f64 = range / ((1u64 << bits) - 1) as f64;
+            assert!(max_error_x <= expected_step / 2.0 * (1.0 + 1e-9));
+            assert_eq!(max_error_y, 0.0);
+            for (value, read) in frame.x.iter().zip(positions.x.iter()) {
+                // Allowing for rounding in min + level * step
+                assert!(
+                    (value - read).abs() <= max_error_x + 1e-9,
+                    "{} read as {} with {} bits",
+                    value,
+                    read,
+                    bits
+                );
+            }
+            assert_eq!(frame.y, positions.y);
+        }
+        assert!(!reader.read_frame(&mut positions).unwrap());
+        fs::remove_file(&filename).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+// Writes snapshots as .npz and stacked .npy files and reads the arrays back
+use std::fs;
+use std::path::PathBuf;
+
+use space_clicker::physics::logger::npy::{NpyLogger, NpyOutput};
+use space_clicker::physics::Vec2D;
+
+const NUM_BODIES: usize = 3;
+
+// A simulation name in the temporary directory, unique to the test
+fn temp_sim_name(test: &str) -> String {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("space_clicker_{}_{}", test, std::process::id()));
+    path.to_str().unwrap().to_string()
+}
+
+fn positions(frame: usize) -> Vec2D {
+    Vec2D {
+        x: (0..NUM_BODIES).map(|i| (frame * 10 + i) as f64).collect(),
+        y: (0..NUM_BODIES).map(|i| -0.5 * i as f64).collect(),
+    }
+}
+
+fn velocities() -> Vec2D {
+    Vec2D {
+        x: vec![0.25; NUM_BODIES],
+        y: vec![-1.0; NUM_BODIES],
+    }
+}
+
+const IDS: [u64; NUM_BODIES] = [4, 8, u64::MAX];
+
+// Checks the .npy header and returns its dict and the array data after it
+fn read_npy(npy: &[u8]) -> (String, &[u8]) {
+    assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+    let header_len: usize = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    // The data starts on a 64 byte boundary
+    assert_eq!((10 + header_len) % 64, 0);
+    let header: &str = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+    assert!(header.ends_with('\n'));
+    (header.trim_end().to_string(), &npy[10 + header_len..])
+}
+
+fn assert_array(npy: &[u8], descr: &str, shape: &str, data: &[u8]) {
+    let (header, array) = read_npy(npy);
+    assert_eq!(
+        header,
+        format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape
+        )
+    );
+    assert_eq!(array, data);
+}
+
+fn f64_bytes(values: impl IntoIterator<Item = f64>) -> Vec<u8> {
+    values.into_iter().flat_map(f64::to_le_bytes).collect()
+}
+
+// Interleaves x and y, as in a (bodies, 2) array
+fn xy_bytes(values: &Vec2D) -> Vec<u8> {
+    f64_bytes((0..NUM_BODIES).flat_map(|i| [values.x[i], values.y[i]]))
+}
+
+fn id_bytes() -> Vec<u8> {
+    IDS.iter().flat_map(|id| id.to_le_bytes()).collect()
+}
+
+// Splits a stored zip archive into its file names and contents
+fn read_zip(zip: &[u8]) -> Vec<(String, &[u8])> {
+    let mut files: Vec<(String, &[u8])> = Vec::new();
+    let mut position: usize = 0;
+    while zip[position..position + 4] == 0x04034b50u32.to_le_bytes() {
+        let field = |offset: usize, len: usize| -> usize {
+            let mut bytes: [u8; 4] = [0; 4];
+            bytes[..len].copy_from_slice(&zip[position + offset..position + offset + len]);
+            u32::from_le_bytes(bytes) as usize
+        };
+        // Stored, with equal compressed and uncompressed sizes
+        assert_eq!(field(8, 2), 0);
+        assert_eq!(field(18, 4), field(22, 4));
+        let (size, name_len, extra_len) = (field(18, 4), field(26, 2), field(28, 2));
+        let name_start: usize = position + 30;
+        let data_start: usize = name_start + name_len + extra_len;
+        let name: String =
+            String::from_utf8(zip[name_start..name_start + name_len].to_vec()).unwrap();
+        files.push((name, &zip[data_start..data_start + size]));
+        position = data_start + size;
+    }
+    // The central directory follows the files
+    assert_eq!(zip[position..position + 4], 0x02014b50u32.to_le_bytes());
+    files
+}
+
+#[test]
+fn npz_frames_hold_each_array() {
+    let sim_name: String = temp_sim_name("npz");
+    let mut logger: NpyLogger = NpyLogger::create(&sim_name, NpyOutput::Npz, NUM_BODIES).unwrap();
+    for frame in 0..2 {
+        logger.log(&positions(frame), &velocities(), &IDS).unwrap();
+    }
+    logger.finish().unwrap();
+    let out_dir: String = format!("{}_npz", sim_name);
+    for frame in 0..2 {
+        let zip: Vec<u8> = fs::read(format!("{}/frame_{:05}.npz", out_dir, frame)).unwrap();
+        let files: Vec<(String, &[u8])> = read_zip(&zip);
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["positions.npy", "velocities.npy", "masses.npy", "ids.npy"]
+        );
+        assert_array(files[0].1, "<f8", "(3, 2)", &xy_bytes(&positions(frame)));
+        assert_array(files[1].1, "<f8", "(3, 2)", &xy_bytes(&velocities()));
+        assert_array(files[2].1, "<f8", "(3,)", &f64_bytes([1.0; NUM_BODIES]));
+        assert_array(files[3].1, "<u8", "(3,)", &id_bytes());
+    }
+    fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn stacked_arrays_grow_along_the_first_axis() {
+    let sim_name: String = temp_sim_name("stacked");
+    let mut logger: NpyLogger =
+        NpyLogger::create(&sim_name, NpyOutput::Stacked, NUM_BODIES).unwrap();
+    for frame in 0..3 {
+        logger.log(&positions(frame), &velocities(), &IDS).unwrap();
+    }
+    logger.finish().unwrap();
+    drop(logger);
+    let read = |name: &str| -> Vec<u8> {
+        let filename: String = format!("{}_{}.npy", sim_name, name);
+        let npy: Vec<u8> = fs::read(&filename).unwrap();
+        fs::remove_file(&filename).unwrap();
+        npy
+    };
+    let stacked_positions: Vec<u8> = (0..3)
+        .flat_map(|frame| xy_bytes(&positions(frame)))
+        .collect();
+    assert_array(&read("positions"), "<f8", "(3, 3, 2)", &stacked_positions);
+    assert_array(
+        &read("velocities"),
+        "<f8",
+        "(3, 3, 2)",
+        &xy_bytes(&velocities()).repeat(3),
+    );
+    assert_array(
+        &read("masses"),
+        "<f8",
+        "(3, 3)",
+        &f64_bytes([1.0; 3 * NUM_BODIES]),
+    );
+    assert_array(&read("ids"), "<u8", "(3, 3)", &id_bytes().repeat(3));
+}
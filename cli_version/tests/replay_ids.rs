@@ -0,0 +1,79 @@
+// Runs a simulation with its own body ids and checks replays identify the bodies by them
+use std::fs;
+use std::path::PathBuf;
+
+use space_clicker::convert::{self, ConvertOptions};
+use space_clicker::input;
+use space_clicker::physics::Vec2D;
+use space_clicker::simulation::{Simulation, SimulationBuilder};
+
+const INPUT: &str = r#"{
+    "num_bodies": 4,
+    "num_steps": 3,
+    "write_interval": 1,
+    "delta_t": 0.01,
+    "softening": 0.1,
+    "gravity": 1.0,
+    "log_energy_conservation": false,
+    "theta": 0.5,
+    "position_log": { "quantized": 16 },
+    "removal": { "max_distance": 100.0 }
+}"#;
+
+#[test]
+fn converted_frames_keep_the_ids_of_the_run() {
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("space_clicker_replay_ids_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let sim_name: String = dir.join("sim").to_str().unwrap().to_string();
+    fs::write(format!("{}.json", sim_name), INPUT).unwrap();
+
+    let constants: input::Constants = input::parse_input(&format!("{}.json", sim_name)).unwrap();
+    let mut simulation: Simulation = SimulationBuilder::new(constants)
+        .bodies(
+            Vec2D {
+                x: vec![-1.0, 1.0, 0.0, 200.0],
+                y: vec![0.0, 0.0, 1.0, 0.0],
+            },
+            Vec2D::new(4),
+        )
+        .ids(vec![70, 7, 700, 7000])
+        .output(&sim_name)
+        .build()
+        .unwrap();
+    for _ in 0..3 {
+        simulation.step().unwrap();
+    }
+    simulation.finish().unwrap();
+    // The distant body is removed after the first step
+    assert_eq!(simulation.ids(), [70, 7, 700]);
+
+    let out_dir: String = dir.join("csv").to_str().unwrap().to_string();
+    let args: Vec<String> = ["--format", "csv", "--out", &out_dir]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    convert::convert(&sim_name, &ConvertOptions::parse(&args).unwrap()).unwrap();
+    let ids_in_frame = |frame: usize| -> Vec<u64> {
+        let csv: String =
+            fs::read_to_string(format!("{}/frame_{:05}.csv", out_dir, frame)).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().ends_with(",id"));
+        lines
+            .map(|line| line.rsplit(',').next().unwrap().parse().unwrap())
+            .collect()
+    };
+    assert_eq!(ids_in_frame(0), [70, 7, 700, 7000]);
+    assert_eq!(ids_in_frame(2), [70, 7, 700]);
+
+    // The VTK series index is named after the simulation, without its directory
+    let out_dir: String = dir.join("vtk").to_str().unwrap().to_string();
+    let args: Vec<String> = ["--format", "vtk", "--out", &out_dir]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    convert::convert(&sim_name, &ConvertOptions::parse(&args).unwrap()).unwrap();
+    let pvd: String = fs::read_to_string(dir.join("vtk").join("sim.pvd")).unwrap();
+    assert!(pvd.contains(r#"file="frame_00002.vtk""#), "{}", pvd);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,102 @@
+// Builds simulations in memory and runs them through the library API
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use space_clicker::input;
+use space_clicker::physics::Vec2D;
+use space_clicker::simulation::{Control, Simulation, SimulationBuilder};
+use space_clicker::timing::TimingReport;
+
+// The constants of an input file named after the test
+fn constants(test: &str) -> input::Constants {
+    let filename: String = std::env::temp_dir()
+        .join(format!(
+            "space_clicker_{}_{}.json",
+            test,
+            std::process::id()
+        ))
+        .to_str()
+        .unwrap()
+        .to_string();
+    std::fs::write(
+        &filename,
+        r#"{
+            "num_bodies": 3,
+            "num_steps": 10,
+            "write_interval": 1,
+            "delta_t": 0.01,
+            "softening": 0.1,
+            "gravity": 1.0,
+            "log_energy_conservation": false,
+            "theta": 0.5
+        }"#,
+    )
+    .unwrap();
+    let constants: input::Constants = input::parse_input(&filename).unwrap();
+    std::fs::remove_file(&filename).unwrap();
+    constants
+}
+
+fn simulation(test: &str) -> Simulation {
+    SimulationBuilder::new(constants(test))
+        .bodies(
+            Vec2D {
+                x: vec![-1.0, 1.0, 0.0],
+                y: vec![0.0, 0.0, 1.0],
+            },
+            Vec2D::new(3),
+        )
+        .ids(vec![5, 6, 7])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn run_until_calls_back_after_every_step() {
+    let mut simulation: Simulation = simulation("run_until");
+    assert_eq!(simulation.num_bodies(), 3);
+    assert_eq!(simulation.ids(), [5, 6, 7]);
+    // Each callback sees the step that just finished
+    let steps_seen: Rc<RefCell<Vec<usize>>> = Rc::new(RefCell::new(Vec::new()));
+    let steps: Rc<RefCell<Vec<usize>>> = Rc::clone(&steps_seen);
+    simulation.on_step(move |simulation| {
+        steps.borrow_mut().push(simulation.step_count());
+        Control::Continue
+    });
+    simulation.run_until(0.05).unwrap();
+    assert_eq!(simulation.step_count(), 5);
+    assert!((simulation.time() - 0.05).abs() < 1e-12);
+    assert_eq!(*steps_seen.borrow(), [1, 2, 3, 4, 5]);
+    // The bodies attract each other
+    assert!(simulation.positions().x[0] > -1.0);
+    // Running on continues from the current time
+    simulation.run_until(0.08).unwrap();
+    assert_eq!(simulation.step_count(), 8);
+    let report: TimingReport = simulation.timing_report();
+    assert_eq!(report.num_steps, 8);
+    assert_eq!(report.num_bodies, 3);
+    simulation.finish().unwrap();
+}
+
+#[test]
+fn a_callback_can_stop_the_run() {
+    let mut simulation: Simulation = simulation("stop");
+    simulation.on_step(|simulation| match simulation.step_count() {
+        3 => Control::Stop,
+        _ => Control::Continue,
+    });
+    simulation.run_until(1.0).unwrap();
+    assert_eq!(simulation.step_count(), 3);
+    assert_eq!(simulation.timing_report().num_steps, 3);
+    // Stepping directly is counted too
+    assert_eq!(simulation.step().unwrap(), Control::Continue);
+    assert_eq!(simulation.timing_report().num_steps, 4);
+}
+
+#[test]
+fn mismatched_bodies_are_rejected() {
+    let result = SimulationBuilder::new(constants("mismatched"))
+        .bodies(Vec2D::new(3), Vec2D::new(2))
+        .build();
+    assert!(result.is_err());
+}
@@ -0,0 +1,182 @@
+// Writes GADGET and TIPSY snapshots and reads them back, and converts runs to them
+use std::fs;
+use std::path::PathBuf;
+
+use space_clicker::convert::gadget::{read_gadget, write_gadget};
+use space_clicker::convert::tipsy::{read_tipsy, write_tipsy};
+use space_clicker::convert::{self, ConvertOptions};
+use space_clicker::input;
+use space_clicker::physics::Vec2D;
+use space_clicker::replay::Snapshot;
+use space_clicker::simulation::{Simulation, SimulationBuilder};
+
+// A file name in the temporary directory, unique to the test
+fn temp_filename(test: &str) -> String {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("space_clicker_{}_{}", test, std::process::id()));
+    path.to_str().unwrap().to_string()
+}
+
+fn snapshot(masses: Vec<f64>, ids: Vec<u64>) -> Snapshot {
+    let num_bodies: usize = masses.len();
+    let mut snapshot: Snapshot = Snapshot::new(num_bodies);
+    snapshot.time = 2.5;
+    let mut velocities: Vec2D = Vec2D::new(num_bodies);
+    for i in 0..num_bodies {
+        snapshot.positions.x[i] = i as f64 * 1.5 - 3.0;
+        snapshot.positions.y[i] = -(i as f64) * 0.25;
+        velocities.x[i] = 0.125 * i as f64;
+        velocities.y[i] = -1.0 + i as f64;
+    }
+    snapshot.velocities = Some(velocities);
+    snapshot.masses = Some(masses);
+    snapshot.ids = Some(ids);
+    snapshot
+}
+
+// Positions, velocities and masses are stored as f32, which holds these values exactly
+fn assert_same_bodies(written: &Snapshot, read: &Snapshot) {
+    assert_eq!(read.time, written.time);
+    assert_eq!(read.positions.x, written.positions.x);
+    assert_eq!(read.positions.y, written.positions.y);
+    let (written_velocities, read_velocities) =
+        (written.velocities.as_ref(), read.velocities.as_ref());
+    assert_eq!(written_velocities.unwrap().x, read_velocities.unwrap().x);
+    assert_eq!(written_velocities.unwrap().y, read_velocities.unwrap().y);
+    assert_eq!(written.masses, read.masses);
+}
+
+#[test]
+fn gadget_roundtrip() {
+    for format2 in [false, true] {
+        for (masses, ids) in [
+            // Variable masses in a mass block, 32 bit ids
+            (vec![1.0, 2.0, 0.5, 4.0], vec![3, 1, 4, 1_000_000]),
+            // A uniform mass in the header, 64 bit ids
+            (vec![0.75; 4], vec![0, u32::MAX as u64 + 1, 7, u64::MAX]),
+        ] {
+            let filename: String = temp_filename(&format!("gadget_{}", format2));
+            let written: Snapshot = snapshot(masses, ids);
+            write_gadget(&written, &filename, format2).unwrap();
+            let read: Snapshot = read_gadget(&filename).unwrap();
+            fs::remove_file(&filename).unwrap();
+            assert_same_bodies(&written, &read);
+            assert_eq!(written.ids, read.ids);
+        }
+    }
+}
+
+#[test]
+fn gadget_negative_count_is_rejected() {
+    let filename: String = temp_filename("gadget_negative");
+    write_gadget(&snapshot(vec![1.0; 2], vec![0, 1]), &filename, false).unwrap();
+    // The first particle count follows the header's record length marker
+    let mut data: Vec<u8> = fs::read(&filename).unwrap();
+    data[4..8].copy_from_slice(&(-1i32).to_le_bytes());
+    fs::write(&filename, &data).unwrap();
+    let result: Result<Snapshot, String> = read_gadget(&filename);
+    fs::remove_file(&filename).unwrap();
+    match result {
+        Ok(_) => panic!("A negative particle count was accepted"),
+        Err(err) => assert!(err.contains("negative particle count"), "{}", err),
+    }
+}
+
+#[test]
+fn tipsy_roundtrip() {
+    let filename: String = temp_filename("tipsy");
+    let written: Snapshot = snapshot(vec![1.0, 2.0, 0.5], vec![10, 20, 30]);
+    write_tipsy(&written, &filename, 0.1).unwrap();
+    let read: Snapshot = read_tipsy(&filename).unwrap();
+    fs::remove_file(&filename).unwrap();
+    assert_same_bodies(&written, &read);
+    // TIPSY has no ids, so bodies are identified by their index
+    assert_eq!(read.ids, None);
+}
+
+// Runs three steps writing the given NumPy output and converts the run to CSV
+// and VTK, checking the frames hold the velocities and masses
+fn convert_with_velocities(npy_output: &str) {
+    let dir: PathBuf = PathBuf::from(temp_filename(&format!("convert_{}", npy_output)));
+    fs::create_dir_all(&dir).unwrap();
+    let sim_name: String = dir.join("sim").to_str().unwrap().to_string();
+    let input: String = format!(
+        r#"{{
+            "num_bodies": 3,
+            "num_steps": 3,
+            "write_interval": 1,
+            "delta_t": 0.01,
+            "softening": 0.1,
+            "gravity": 1.0,
+            "log_energy_conservation": false,
+            "theta": 0.5,
+            "npy_output": "{}"
+        }}"#,
+        npy_output
+    );
+    fs::write(format!("{}.json", sim_name), input).unwrap();
+    let constants: input::Constants = input::parse_input(&format!("{}.json", sim_name)).unwrap();
+    let initial_velocities: Vec2D = Vec2D {
+        x: vec![0.5, -0.25, 0.0],
+        y: vec![0.0, 0.75, -1.5],
+    };
+    let mut simulation: Simulation = SimulationBuilder::new(constants)
+        .bodies(
+            Vec2D {
+                x: vec![-1.0, 1.0, 0.0],
+                y: vec![0.0, 0.0, 1.0],
+            },
+            initial_velocities.clone(),
+        )
+        .output(&sim_name)
+        .build()
+        .unwrap();
+    simulation.step().unwrap();
+    simulation.step().unwrap();
+    // The third frame is written at the start of the third step
+    let last_velocities: Vec2D = simulation.velocities().clone();
+    simulation.step().unwrap();
+    simulation.finish().unwrap();
+
+    let convert_to = |format: &str| -> String {
+        let out_dir: String = dir.join(format).to_str().unwrap().to_string();
+        let args: Vec<String> = ["--format", format, "--out", &out_dir]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        convert::convert(&sim_name, &ConvertOptions::parse(&args).unwrap()).unwrap();
+        out_dir
+    };
+    let csv_dir: String = convert_to("csv");
+    for (frame, velocities) in [(0, &initial_velocities), (2, &last_velocities)] {
+        let csv: String =
+            fs::read_to_string(format!("{}/frame_{:05}.csv", csv_dir, frame)).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), "index,x,y,vx,vy,mass,id");
+        for (i, line) in lines.enumerate() {
+            let columns: Vec<f64> = line
+                .split(',')
+                .map(|value| value.parse().unwrap())
+                .collect();
+            assert_eq!(columns[3], velocities.x[i]);
+            assert_eq!(columns[4], velocities.y[i]);
+            assert_eq!(columns[5], 1.0);
+        }
+    }
+    let vtk_dir: String = convert_to("vtk");
+    let vtk: Vec<u8> = fs::read(format!("{}/frame_00000.vtk", vtk_dir)).unwrap();
+    let vtk: String = String::from_utf8_lossy(&vtk).into_owned();
+    assert!(vtk.contains("POINT_DATA 3\nVECTORS velocity double\n"));
+    assert!(vtk.contains("SCALARS mass double 1\n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn convert_reads_velocities_from_stacked_arrays() {
+    convert_with_velocities("stacked");
+}
+
+#[test]
+fn convert_reads_velocities_from_npz_frames() {
+    convert_with_velocities("npz");
+}
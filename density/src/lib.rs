@@ -0,0 +1,203 @@
+// Density maps: bodies are accumulated into a grid of pixels, optionally
+// smoothed, and the counts are mapped to colours through a scaling and a colour map.
+
+// Colour map control points, evenly spaced from 0 (empty) to 1 (densest)
+const GREY: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+const INFERNO: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [31, 12, 72],
+    [85, 15, 109],
+    [136, 34, 106],
+    [186, 54, 85],
+    [227, 89, 51],
+    [249, 140, 10],
+    [249, 201, 50],
+    [252, 255, 164],
+];
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 45, 123],
+    [59, 82, 139],
+    [44, 114, 142],
+    [33, 145, 140],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+const STARS: [[u8; 3]; 5] = [
+    [0, 0, 0],
+    [40, 50, 110],
+    [155, 176, 255],
+    [255, 244, 234],
+    [255, 204, 111],
+];
+
+// Kernel half-widths are this many standard deviations
+const KERNEL_SIGMAS: f64 = 3.0;
+
+#[derive(Clone, Copy)]
+pub enum ColourMap {
+    Grey,
+    Inferno,
+    Viridis,
+    Stars,
+}
+impl ColourMap {
+    pub fn from_name(name: &str) -> Option<ColourMap> {
+        match name {
+            "grey" => Some(ColourMap::Grey),
+            "inferno" => Some(ColourMap::Inferno),
+            "viridis" => Some(ColourMap::Viridis),
+            "stars" => Some(ColourMap::Stars),
+            _ => None,
+        }
+    }
+    fn control_points(&self) -> &'static [[u8; 3]] {
+        match self {
+            ColourMap::Grey => &GREY,
+            ColourMap::Inferno => &INFERNO,
+            ColourMap::Viridis => &VIRIDIS,
+            ColourMap::Stars => &STARS,
+        }
+    }
+    // Linearly interpolates the colour at t in [0, 1]
+    pub fn colour(&self, t: f64) -> [u8; 3] {
+        interpolate_colour(self.control_points(), t)
+    }
+}
+
+// Linearly interpolates the colour at t in [0, 1] between at least two
+// evenly spaced control points
+pub fn interpolate_colour(points: &[[u8; 3]], t: f64) -> [u8; 3] {
+    let position: f64 = t.clamp(0.0, 1.0) * (points.len() - 1) as f64;
+    let index: usize = (position as usize).min(points.len() - 2);
+    let fraction: f64 = position - index as f64;
+    let mut colour: [u8; 3] = [0; 3];
+    for (channel, value) in colour.iter_mut().enumerate() {
+        let low: f64 = points[index][channel] as f64;
+        let high: f64 = points[index + 1][channel] as f64;
+        *value = (low + (high - low) * fraction).round() as u8;
+    }
+    colour
+}
+
+// How density is mapped to [0, 1] before colouring
+#[derive(Clone, Copy)]
+pub enum Scaling {
+    Linear,
+    Log,
+    Asinh,
+}
+impl Scaling {
+    pub fn from_name(name: &str) -> Option<Scaling> {
+        match name {
+            "linear" => Some(Scaling::Linear),
+            "log" => Some(Scaling::Log),
+            "asinh" => Some(Scaling::Asinh),
+            _ => None,
+        }
+    }
+    fn apply(&self, density: f64) -> f64 {
+        match self {
+            Scaling::Linear => density,
+            Scaling::Log => density.ln_1p(),
+            Scaling::Asinh => density.asinh(),
+        }
+    }
+}
+
+pub struct DensityMap {
+    pub width: usize,
+    pub height: usize,
+    grid: Vec<f64>,
+    scratch: Vec<f64>,
+}
+impl DensityMap {
+    pub fn new(width: usize, height: usize) -> DensityMap {
+        DensityMap {
+            width,
+            height,
+            grid: vec![0.0; width * height],
+            scratch: vec![0.0; width * height],
+        }
+    }
+    // Resizes the grid, discarding its contents
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.grid = vec![0.0; width * height];
+        self.scratch = vec![0.0; width * height];
+    }
+    // Adds one count per body to the pixel it lands in, where centre is the
+    // simulation position at the centre of the grid and scale is pixels per unit length
+    pub fn accumulate(&mut self, x: &[f64], y: &[f64], centre: (f64, f64), scale: f64) {
+        self.grid.iter_mut().for_each(|cell| *cell = 0.0);
+        let half_width: f64 = self.width as f64 / 2.0;
+        let half_height: f64 = self.height as f64 / 2.0;
+        for i in 0..x.len() {
+            let canvas_x: f64 = (x[i] - centre.0) * scale + half_width;
+            let canvas_y: f64 = (y[i] - centre.1) * scale + half_height;
+            if canvas_x >= 0.0
+                && canvas_x < self.width as f64
+                && canvas_y >= 0.0
+                && canvas_y < self.height as f64
+            {
+                self.grid[canvas_y as usize * self.width + canvas_x as usize] += 1.0;
+            }
+        }
+    }
+    // Smooths the grid with a gaussian kernel of the given standard deviation in pixels
+    pub fn smooth(&mut self, sigma: f64) {
+        if sigma <= 0.0 {
+            return;
+        }
+        let radius: usize = (sigma * KERNEL_SIGMAS).ceil() as usize;
+        let mut kernel: Vec<f64> = (0..=2 * radius)
+            .map(|i| {
+                let offset: f64 = i as f64 - radius as f64;
+                (-offset * offset / (2.0 * sigma * sigma)).exp()
+            })
+            .collect();
+        let total: f64 = kernel.iter().sum();
+        kernel.iter_mut().for_each(|weight| *weight /= total);
+        // The gaussian is separable so blur the rows then the columns
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let mut sum: f64 = 0.0;
+                for (k, weight) in kernel.iter().enumerate() {
+                    let source: isize = column as isize + k as isize - radius as isize;
+                    if source >= 0 && (source as usize) < self.width {
+                        sum += weight * self.grid[row * self.width + source as usize];
+                    }
+                }
+                self.scratch[row * self.width + column] = sum;
+            }
+        }
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let mut sum: f64 = 0.0;
+                for (k, weight) in kernel.iter().enumerate() {
+                    let source: isize = row as isize + k as isize - radius as isize;
+                    if source >= 0 && (source as usize) < self.height {
+                        sum += weight * self.scratch[source as usize * self.width + column];
+                    }
+                }
+                self.grid[row * self.width + column] = sum;
+            }
+        }
+    }
+    // Writes the scaled and coloured grid into an RGBA buffer of width * height * 4 bytes
+    pub fn to_rgba(&self, scaling: Scaling, colour_map: ColourMap, rgba: &mut [u8]) {
+        let max: f64 = scaling.apply(self.grid.iter().cloned().fold(0.0, f64::max));
+        for (i, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+            let t: f64 = if max > 0.0 {
+                scaling.apply(self.grid[i]) / max
+            } else {
+                0.0
+            };
+            pixel[..3].copy_from_slice(&colour_map.colour(t));
+            pixel[3] = 255;
+        }
+    }
+}
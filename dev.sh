@@ -3,11 +3,11 @@
 echo "OSTYPE: $OSTYPE"
 if [ "$OSTYPE" == "darwin20" ]; then
 	echo "Running on mac"
-    fswatch -o -r ./src/* | xargs -n1 -I{} ./build.sh
+    fswatch -o -r ./src/* ./density/src/* | xargs -n1 -I{} ./build.sh
 fi
 
 # if on linux run while inotifywait -e close_write myfile.py; do ./build.sh; done
 if [ "$OSTYPE" == "linux-gnu" ]; then
 	echo "Running on linux"
-    while inotifywait -e close_write ./src/*; do ./build.sh; done
+    while inotifywait -e close_write ./src/* ./density/src/*; do ./build.sh; done
 fi
\ No newline at end of file
@@ -0,0 +1,126 @@
+#ifndef SPACE_CLICKER_H
+#define SPACE_CLICKER_H
+
+/* Generated by build.rs with cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct SpaceClickerSimulation SpaceClickerSimulation;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates an empty simulation.
+SpaceClickerSimulation *space_clicker_create(void);
+
+// Frees a simulation. Passing NULL does nothing.
+//
+// # Safety
+// `simulation` must be NULL or a handle from `space_clicker_create` that has
+// not already been destroyed.
+void space_clicker_destroy(SpaceClickerSimulation *simulation);
+
+// Adds `count` bodies as one spawn batch and returns the new number of
+// bodies. `vx` and `vy` may be NULL for bodies at rest, `masses` may be NULL
+// for bodies of mass 1 and `fixed` may be NULL for bodies that are free to
+// move. Bodies with `fixed` set are pinned in place.
+//
+// # Safety
+// `simulation` must be a valid handle. `x` and `y`, and `vx`, `vy` and
+// `masses` unless NULL, must point to at least `count` doubles. `fixed`
+// must be NULL or point to at least `count` bools.
+size_t space_clicker_add_bodies(SpaceClickerSimulation *simulation,
+                                const double *x,
+                                const double *y,
+                                const double *vx,
+                                const double *vy,
+                                const double *masses,
+                                const bool *fixed,
+                                size_t count);
+
+// Sets the time step.
+//
+// # Safety
+// `simulation` must be a valid handle.
+void space_clicker_set_dt(SpaceClickerSimulation *simulation, double dt);
+
+// Sets the gravitational constant.
+//
+// # Safety
+// `simulation` must be a valid handle.
+void space_clicker_set_gravity(SpaceClickerSimulation *simulation, double gravity);
+
+// Removes bodies after each step that are further than `max_distance` from
+// the centre of mass, if it is positive, and, if `remove_unbound`, bodies
+// moving away from the centre of mass with positive energy. Both are off by
+// default. Removed bodies keep the order of the rest, so use
+// `space_clicker_copy_ids` to tell which bodies remain.
+//
+// # Safety
+// `simulation` must be a valid handle.
+void space_clicker_set_removal(SpaceClickerSimulation *simulation,
+                               double max_distance,
+                               bool remove_unbound);
+
+// Sets whether the removal policy removes pinned bodies too. Pinned bodies
+// are kept by default.
+//
+// # Safety
+// `simulation` must be a valid handle.
+void space_clicker_set_remove_pinned(SpaceClickerSimulation *simulation, bool remove_pinned);
+
+// Advances the simulation by `num_steps` time steps.
+//
+// # Safety
+// `simulation` must be a valid handle.
+void space_clicker_step(SpaceClickerSimulation *simulation, size_t num_steps);
+
+// Returns the number of bodies.
+//
+// # Safety
+// `simulation` must be a valid handle.
+size_t space_clicker_num_bodies(const SpaceClickerSimulation *simulation);
+
+// Copies up to `capacity` body positions into `x` and `y` and returns the
+// number copied.
+//
+// # Safety
+// `simulation` must be a valid handle. `x` and `y` must point to at least
+// `capacity` doubles.
+size_t space_clicker_copy_positions(const SpaceClickerSimulation *simulation,
+                                    double *x,
+                                    double *y,
+                                    size_t capacity);
+
+// Copies up to `capacity` body velocities into `vx` and `vy` and returns
+// the number copied.
+//
+// # Safety
+// `simulation` must be a valid handle. `vx` and `vy` must point to at least
+// `capacity` doubles.
+size_t space_clicker_copy_velocities(const SpaceClickerSimulation *simulation,
+                                     double *vx,
+                                     double *vy,
+                                     size_t capacity);
+
+// Copies up to `capacity` body ids into `ids` and returns the number copied.
+// A body keeps its id for its whole life, so ids match bodies across steps
+// even if their indexes change.
+//
+// # Safety
+// `simulation` must be a valid handle. `ids` must point to at least
+// `capacity` uint64_t values.
+size_t space_clicker_copy_ids(const SpaceClickerSimulation *simulation,
+                              uint64_t *ids,
+                              size_t capacity);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SPACE_CLICKER_H */
@@ -3,6 +3,9 @@ pub const SOFTENING: f64 = 0.1;
 pub const THETA: f64 = 0.9; // Used in acceleration calculation, the smaller the more accurate
 pub const BODIES_PER_SPAWN: usize = 2; // The number of bodies to spawn at a time
 pub const START_BOX_SIZE: f64 = 50.0; // The size of the box that contains all the bodies at the start
+pub const SPAWN_RING_WIDTH: f64 = 0.1; // The width of a spawned ring as a fraction of its radius
+pub const SPAWN_PREVIEW_STEPS: usize = 300; // The number of steps ahead the path of spawned bodies is predicted by default
+pub const SPAWN_PLUMMER_CUTOFF: f64 = 0.99; // The fraction of a Plummer blob's mass spawned, leaving out the sparse outskirts
 
 // For the Barnes-Hut tree construction
 pub const TREE_GROWTH_INCREMENT: usize = 100;
@@ -32,3 +35,17 @@ pub const BODY_DRAW_SIZE_MOBILE: i32 = 3;
 pub const SPAWN_BODY_DRAW_SIZE: i32 = 3;
 pub const SPAWN_BODY_DRAW_SIZE_MOBILE: i32 = 20;
 pub const SPAWN_BODY_COLOR: &str = "rgb(255,0,0)";
+pub const SPAWN_PREVIEW_COLOR: &str = "rgba(255,0,0,0.6)";
+pub const DRAW_BUFFER_STRIDE: usize = 4; // Floats per body in the draw buffer: canvas x, canvas y, colour index, size
+
+// For moving the view
+pub const CAMERA_SMOOTHING: f64 = 0.1; // Fraction of the way the view moves to its target each frame
+
+// For the erase, grab and fling tools
+pub const TOOL_BRUSH_RADIUS: f64 = 30.0; // The radius of the brush in canvas pixels by default
+pub const TOOL_BRUSH_SEGMENTS: usize = 32; // The number of lines the brush outline is drawn with
+pub const TOOL_BRUSH_COLOR: &str = "rgba(255,255,255,0.6)";
+pub const PLACE_MASS: f64 = 1000.0; // The mass of bodies added with the place tool by default
+
+// For drawing the trails of tracked bodies
+pub const TRAIL_LENGTH: usize = 500; // The number of steps each trail covers by default
@@ -0,0 +1,246 @@
+// C API for driving the simulation core from native code.
+//
+// A simulation is an opaque handle from space_clicker_create that must be
+// freed with space_clicker_destroy. Handles are independent of the web
+// simulation and of each other, but a single handle must not be used from
+// more than one thread at a time. The C header is generated by build.rs into
+// include/space_clicker.h.
+
+use std::ptr;
+use std::slice;
+
+use crate::simulation::Simulation;
+
+// The default time step and gravity of a new simulation
+const DEFAULT_DT: f64 = 0.1;
+const DEFAULT_GRAVITY: f64 = 1.0;
+
+/// Creates an empty simulation.
+#[no_mangle]
+pub extern "C" fn space_clicker_create() -> *mut Simulation {
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.dt = DEFAULT_DT;
+    simulation.gravity = DEFAULT_GRAVITY;
+    Box::into_raw(Box::new(simulation))
+}
+
+/// Frees a simulation. Passing NULL does nothing.
+///
+/// # Safety
+/// `simulation` must be NULL or a handle from `space_clicker_create` that has
+/// not already been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_destroy(simulation: *mut Simulation) {
+    if !simulation.is_null() {
+        drop(Box::from_raw(simulation));
+    }
+}
+
+/// Adds `count` bodies as one spawn batch and returns the new number of
+/// bodies. `vx` and `vy` may be NULL for bodies at rest, `masses` may be NULL
+/// for bodies of mass 1 and `fixed` may be NULL for bodies that are free to
+/// move. Bodies with `fixed` set are pinned in place.
+///
+/// # Safety
+/// `simulation` must be a valid handle. `x` and `y`, and `vx`, `vy` and
+/// `masses` unless NULL, must point to at least `count` doubles. `fixed`
+/// must be NULL or point to at least `count` bools.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn space_clicker_add_bodies(
+    simulation: *mut Simulation,
+    x: *const f64,
+    y: *const f64,
+    vx: *const f64,
+    vy: *const f64,
+    masses: *const f64,
+    fixed: *const bool,
+    count: usize,
+) -> usize {
+    let simulation: &mut Simulation = match simulation.as_mut() {
+        Some(simulation) => simulation,
+        None => return 0,
+    };
+    if count == 0 || x.is_null() || y.is_null() {
+        return simulation.num_bodies;
+    }
+    let x: &[f64] = slice::from_raw_parts(x, count);
+    let y: &[f64] = slice::from_raw_parts(y, count);
+    let vx: Option<&[f64]> = (!vx.is_null()).then(|| slice::from_raw_parts(vx, count));
+    let vy: Option<&[f64]> = (!vy.is_null()).then(|| slice::from_raw_parts(vy, count));
+    let masses: Option<&[f64]> = (!masses.is_null()).then(|| slice::from_raw_parts(masses, count));
+    let fixed: Option<&[bool]> = (!fixed.is_null()).then(|| slice::from_raw_parts(fixed, count));
+    simulation.start_batch();
+    for i in 0..count {
+        simulation.add_massive_body(
+            x[i],
+            y[i],
+            vx.map_or(0.0, |vx| vx[i]),
+            vy.map_or(0.0, |vy| vy[i]),
+            masses.map_or(1.0, |masses| masses[i]),
+            fixed.is_some_and(|fixed| fixed[i]),
+        );
+    }
+    simulation.num_bodies
+}
+
+/// Sets the time step.
+///
+/// # Safety
+/// `simulation` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_set_dt(simulation: *mut Simulation, dt: f64) {
+    if let Some(simulation) = simulation.as_mut() {
+        simulation.dt = dt;
+    }
+}
+
+/// Sets the gravitational constant.
+///
+/// # Safety
+/// `simulation` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_set_gravity(simulation: *mut Simulation, gravity: f64) {
+    if let Some(simulation) = simulation.as_mut() {
+        simulation.gravity = gravity;
+    }
+}
+
+/// Removes bodies after each step that are further than `max_distance` from
+/// the centre of mass, if it is positive, and, if `remove_unbound`, bodies
+/// moving away from the centre of mass with positive energy. Both are off by
+/// default. Removed bodies keep the order of the rest, so use
+/// `space_clicker_copy_ids` to tell which bodies remain.
+///
+/// # Safety
+/// `simulation` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_set_removal(
+    simulation: *mut Simulation,
+    max_distance: f64,
+    remove_unbound: bool,
+) {
+    if let Some(simulation) = simulation.as_mut() {
+        simulation.removal.max_distance = (max_distance > 0.0).then_some(max_distance);
+        simulation.removal.remove_unbound = remove_unbound;
+    }
+}
+
+/// Sets whether the removal policy removes pinned bodies too. Pinned bodies
+/// are kept by default.
+///
+/// # Safety
+/// `simulation` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_set_remove_pinned(
+    simulation: *mut Simulation,
+    remove_pinned: bool,
+) {
+    if let Some(simulation) = simulation.as_mut() {
+        simulation.removal.remove_pinned = remove_pinned;
+    }
+}
+
+/// Advances the simulation by `num_steps` time steps.
+///
+/// # Safety
+/// `simulation` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_step(simulation: *mut Simulation, num_steps: usize) {
+    if let Some(simulation) = simulation.as_mut() {
+        for _ in 0..num_steps {
+            simulation.fit_tree_root();
+            simulation.step();
+            simulation.remove_bodies();
+        }
+    }
+}
+
+/// Returns the number of bodies.
+///
+/// # Safety
+/// `simulation` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_num_bodies(simulation: *const Simulation) -> usize {
+    match simulation.as_ref() {
+        Some(simulation) => simulation.num_bodies,
+        None => 0,
+    }
+}
+
+/// Copies up to `capacity` body positions into `x` and `y` and returns the
+/// number copied.
+///
+/// # Safety
+/// `simulation` must be a valid handle. `x` and `y` must point to at least
+/// `capacity` doubles.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_copy_positions(
+    simulation: *const Simulation,
+    x: *mut f64,
+    y: *mut f64,
+    capacity: usize,
+) -> usize {
+    match simulation.as_ref() {
+        Some(simulation) => copy_out(&simulation.x, &simulation.y, x, y, capacity),
+        None => 0,
+    }
+}
+
+/// Copies up to `capacity` body velocities into `vx` and `vy` and returns
+/// the number copied.
+///
+/// # Safety
+/// `simulation` must be a valid handle. `vx` and `vy` must point to at least
+/// `capacity` doubles.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_copy_velocities(
+    simulation: *const Simulation,
+    vx: *mut f64,
+    vy: *mut f64,
+    capacity: usize,
+) -> usize {
+    match simulation.as_ref() {
+        Some(simulation) => copy_out(&simulation.vx, &simulation.vy, vx, vy, capacity),
+        None => 0,
+    }
+}
+
+/// Copies up to `capacity` body ids into `ids` and returns the number copied.
+/// A body keeps its id for its whole life, so ids match bodies across steps
+/// even if their indexes change.
+///
+/// # Safety
+/// `simulation` must be a valid handle. `ids` must point to at least
+/// `capacity` uint64_t values.
+#[no_mangle]
+pub unsafe extern "C" fn space_clicker_copy_ids(
+    simulation: *const Simulation,
+    ids: *mut u64,
+    capacity: usize,
+) -> usize {
+    match simulation.as_ref() {
+        Some(simulation) if !ids.is_null() => {
+            let count: usize = simulation.ids.len().min(capacity);
+            ptr::copy_nonoverlapping(simulation.ids.as_ptr(), ids, count);
+            count
+        }
+        _ => 0,
+    }
+}
+
+unsafe fn copy_out(
+    a: &[f64],
+    b: &[f64],
+    out_a: *mut f64,
+    out_b: *mut f64,
+    capacity: usize,
+) -> usize {
+    if out_a.is_null() || out_b.is_null() {
+        return 0;
+    }
+    let count: usize = a.len().min(capacity);
+    ptr::copy_nonoverlapping(a.as_ptr(), out_a, count);
+    ptr::copy_nonoverlapping(b.as_ptr(), out_b, count);
+    count
+}
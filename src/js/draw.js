@@ -6,6 +6,22 @@ function draw_body(x, y, color, size) {
 	ctx.fillRect(x, y, size, size);
 }
 
+// The colours that draw_body_buffer's colour indexes refer to, filled in from
+// the wasm palette when it is loaded and whenever the colour mode or gradient changes
+var body_colours = [];
+
+// Draws bodies packed as [x0, y0, colour index0, size0, x1, ...]
+function draw_body_buffer(buffer) {
+	let colour_index = -1;
+	for (let i = 0; i < buffer.length; i += 4) {
+		if (buffer[i + 2] !== colour_index) {
+			colour_index = buffer[i + 2];
+			ctx.fillStyle = body_colours[colour_index];
+		}
+		ctx.fillRect(buffer[i], buffer[i + 1], buffer[i + 3], buffer[i + 3]);
+	}
+}
+
 function draw_arrow(x1, y1, x2, y2, color) {
 	// set arrow properties
 	ctx.strokeStyle = color;
@@ -29,7 +45,29 @@ function draw_arrow(x1, y1, x2, y2, color) {
 	ctx.fill();
 }
 
+function draw_image(pixels, width, height) {
+	ctx.putImageData(new ImageData(pixels, width, height), 0, 0);
+}
+
+// Draws a line through points given as [x0, y0, x1, y1, ...]
+function draw_trail(points, color) {
+	ctx.strokeStyle = color;
+	ctx.lineWidth = 1;
+	ctx.lineJoin = "round";
+	ctx.beginPath();
+	ctx.moveTo(points[0], points[1]);
+	for (let i = 2; i < points.length; i += 2) {
+		ctx.lineTo(points[i], points[i + 1]);
+	}
+	ctx.stroke();
+}
+
 function increase_num_bodies(num) {
 	current_num = parseInt(document.getElementById("num-bodies").innerHTML);
 	document.getElementById("num-bodies").innerHTML = current_num + num;
+}
+
+function decrease_num_bodies(num) {
+	current_num = parseInt(document.getElementById("num-bodies").innerHTML);
+	document.getElementById("num-bodies").innerHTML = current_num - num;
 }
\ No newline at end of file
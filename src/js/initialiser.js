@@ -1,4 +1,4 @@
-import init, { create_bodies, render_bodies, on_click, off_click, set_dt, set_gravity, set_spawn_radius, set_spawn_speed, update_mouse_position, set_scale_multiplier } from './space_clicker.js';
+import init, { create_bodies, render_bodies, on_click, off_click, set_dt, set_gravity, set_spawn_radius, set_spawn_speed, set_spawn_shape, spawn_shape, set_spawn_spin, update_mouse_position, set_tool, tool, tool_press, tool_drag, tool_release, set_place_mass, set_place_fixed, set_scale_multiplier, set_draw_density, track_nearest_body, clear_tracked_bodies, set_remove_unbound, set_use_draw_buffer, num_palette_colours, palette_colour, set_colour_mode, colour_mode, set_colour_gradient, set_camera_mode, camera_mode, follow_nearest_body, pan_camera, zoom_camera, pause, resume, step_once, set_time_direction, export_state, import_state, export_scene_code, import_scene_code, num_bodies } from './space_clicker.js';
 
 const NUM_STARTING_BODIES = 10;
 
@@ -13,8 +13,20 @@ const INITIAL_SPAWN_RADIUS = 50;
 const INITIAL_SPAWN_SPEED = 1;
 const SPAWN_SPEED_MULTIPLIER = 0.01;
 
+const NEXT_COLOUR_MODE = { "star": "speed", "speed": "energy", "energy": "acceleration", "acceleration": "batch", "batch": "star" };
+const NEXT_TOOL = { "spawn": "erase", "erase": "grab", "grab": "fling", "fling": "place", "place": "spawn" };
+const NEXT_SPAWN_SHAPE = { "square": "disk", "disk": "ring", "ring": "line", "line": "plummer", "plummer": "square" };
+// Spin as a fraction of the circular orbit speed, positive for anticlockwise
+const SPAWN_SPINS = { "none": 0, "anticlockwise": 1, "clockwise": -1 };
+const NEXT_SPAWN_SPIN = { "none": "anticlockwise", "anticlockwise": "clockwise", "clockwise": "none" };
+
 const BUTTON_SCALE_FACTOR = 1.5;
 
+const CAMERA_ZOOM_FACTOR = 1.1; // Zoom per scroll wheel step
+const NEXT_CAMERA_MODE = { "auto": "manual", "manual": "fixed", "fixed": "auto", "follow": "auto" };
+
+const SCENE_CODE_PREFIX = "scene="; // Shared links end with #scene=<code>
+
 async function run() {
 	let begun = false;
 
@@ -49,6 +61,9 @@ async function run() {
 	}
 	// Initialise the simulation
 	create_bodies(canvas.width, canvas.height, NUM_STARTING_BODIES, is_mobile);
+	// Draw all the bodies with one call per frame
+	load_palette();
+	set_use_draw_buffer(true);
 
 	// VARIABLES
 	set_dt(INITIAL_TIME_STEP * TIME_STEP_MULTIPLIER);
@@ -78,6 +93,11 @@ async function run() {
 		e.target.innerHTML = spawnRadius;
 		set_spawn_radius(spawnRadius);
 	});
+	document.getElementById("place-mass").addEventListener("blur", function (e) {
+		let placeMass = cleanNumberInput(e.target.innerHTML);
+		e.target.innerHTML = placeMass;
+		set_place_mass(placeMass);
+	});
 	document.getElementById("spawn-speed").addEventListener("blur", function (e) {
 		let spawnSpeed = cleanNumberInput(e.target.innerHTML);
 		e.target.innerHTML = spawnSpeed;
@@ -156,17 +176,204 @@ async function run() {
 		set_scale_multiplier(zoom);
 	});
 
-	// Add spawning listeners
+	let draw_density = false;
+	document.getElementById("toggle-density").addEventListener("click", function (e) {
+		draw_density = !draw_density;
+		e.target.innerHTML = draw_density ? "On" : "Off";
+		set_draw_density(draw_density);
+	});
+
+	document.getElementById("clear-trails").addEventListener("click", function (e) {
+		clear_tracked_bodies();
+	});
+
+	let remove_unbound = false;
+	document.getElementById("toggle-removal").addEventListener("click", function (e) {
+		remove_unbound = !remove_unbound;
+		e.target.innerHTML = remove_unbound ? "On" : "Off";
+		set_remove_unbound(remove_unbound);
+	});
+
+	// Time controls, space also pauses and resumes
+	let paused = false;
+	function set_paused(new_paused) {
+		paused = new_paused;
+		document.getElementById("toggle-pause").innerHTML = paused ? "Resume" : "Pause";
+		if (paused) {
+			pause();
+		} else {
+			resume();
+		}
+	}
+	document.getElementById("toggle-pause").addEventListener("click", function (e) {
+		set_paused(!paused);
+	});
+	document.getElementById("step-once").addEventListener("click", function (e) {
+		step_once();
+		paused = true;
+		document.getElementById("toggle-pause").innerHTML = "Resume";
+	});
+	let reversed = false;
+	document.getElementById("toggle-direction").addEventListener("click", function (e) {
+		reversed = !reversed;
+		e.target.innerHTML = reversed ? "Backward" : "Forward";
+		set_time_direction(reversed ? -1 : 1);
+	});
+	document.addEventListener("keydown", function (e) {
+		if (e.code === "Space" && e.target === document.body) {
+			e.preventDefault();
+			set_paused(!paused);
+		}
+	});
+
+	// Save the scene to a file and load it back
+	document.getElementById("save-scene").addEventListener("click", function (e) {
+		let blob = new Blob([export_state()], { type: "application/json" });
+		let link = document.createElement("a");
+		link.href = URL.createObjectURL(blob);
+		link.download = "scene.json";
+		link.click();
+		URL.revokeObjectURL(link.href);
+	});
+	document.getElementById("load-scene").addEventListener("click", function (e) {
+		document.getElementById("scene-file").click();
+	});
+	document.getElementById("scene-file").addEventListener("change", async function (e) {
+		if (e.target.files.length === 0) {
+			return;
+		}
+		let json = await e.target.files[0].text();
+		e.target.value = "";
+		try {
+			import_state(json);
+		} catch (err) {
+			alert(err);
+			return;
+		}
+		show_scene_parameters(JSON.parse(json).parameters);
+	});
+	// Share the scene as a link with the scene code in the fragment
+	document.getElementById("share-scene").addEventListener("click", async function (e) {
+		history.replaceState(null, "", "#" + SCENE_CODE_PREFIX + export_scene_code());
+		try {
+			await navigator.clipboard.writeText(location.href);
+			alert("Copied a link to this scene");
+		} catch (err) {
+			alert("Copy the link to this scene from the address bar");
+		}
+	});
+	// Open a scene shared in the link
+	if (location.hash.startsWith("#" + SCENE_CODE_PREFIX)) {
+		try {
+			import_scene_code(location.hash.slice(SCENE_CODE_PREFIX.length + 1));
+			show_scene_parameters(JSON.parse(export_state()).parameters);
+		} catch (err) {
+			alert("The shared scene couldn't be opened: " + err);
+		}
+	}
+
+	document.getElementById("tool").addEventListener("click", function (e) {
+		set_tool(NEXT_TOOL[tool()]);
+		e.target.innerHTML = tool();
+	});
+	let place_fixed = false;
+	document.getElementById("toggle-place-fixed").addEventListener("click", function (e) {
+		place_fixed = !place_fixed;
+		e.target.innerHTML = place_fixed ? "Pinned" : "Free";
+		set_place_fixed(place_fixed);
+	});
+	document.getElementById("spawn-shape").addEventListener("click", function (e) {
+		set_spawn_shape(NEXT_SPAWN_SHAPE[spawn_shape()]);
+		e.target.innerHTML = spawn_shape();
+	});
+	document.getElementById("spawn-spin").addEventListener("click", function (e) {
+		let spin = NEXT_SPAWN_SPIN[e.target.innerHTML] ?? "none";
+		set_spawn_spin(SPAWN_SPINS[spin]);
+		e.target.innerHTML = spin;
+	});
+
+	// Colour by speed, energy, acceleration or spawn batch along a gradient of three colours
+	document.getElementById("colour-mode").addEventListener("click", function (e) {
+		set_colour_mode(NEXT_COLOUR_MODE[colour_mode()]);
+		e.target.innerHTML = colour_mode();
+		load_palette();
+	});
+	for (let id of ["gradient-low", "gradient-middle", "gradient-high"]) {
+		document.getElementById(id).addEventListener("input", function (e) {
+			let rgb = [];
+			for (let id of ["gradient-low", "gradient-middle", "gradient-high"]) {
+				let hex = document.getElementById(id).value;
+				rgb.push(parseInt(hex.slice(1, 3), 16), parseInt(hex.slice(3, 5), 16), parseInt(hex.slice(5, 7), 16));
+			}
+			set_colour_gradient(new Uint8Array(rgb));
+			load_palette();
+		});
+	}
+
+	document.getElementById("camera-mode").addEventListener("click", function (e) {
+		set_camera_mode(NEXT_CAMERA_MODE[camera_mode()]);
+	});
+
+	// Add spawning listeners. Shift-click tracks the nearest body, ctrl-click
+	// follows it and right or alt drags pan the view instead.
+	let spawning_click = false;
+	let tool_click = false;
+	let panning = false;
+	let pan_x = 0;
+	let pan_y = 0;
 	canvas.addEventListener("mousedown", function (e) {
-		on_click(e.clientX, e.clientY);
+		spawning_click = false;
+		if (e.button === 2 || e.altKey) {
+			panning = true;
+			pan_x = e.clientX;
+			pan_y = e.clientY;
+			return;
+		}
+		if (e.shiftKey) {
+			track_nearest_body(e.clientX, e.clientY);
+			return;
+		}
+		if (e.ctrlKey) {
+			follow_nearest_body(e.clientX, e.clientY);
+			return;
+		}
 		update_mouse_position(e.clientX, e.clientY);
+		if (tool() !== "spawn") {
+			tool_click = true;
+			tool_press(e.clientX, e.clientY);
+			return;
+		}
+		spawning_click = true;
+		on_click(e.clientX, e.clientY);
 	});
 	canvas.addEventListener("mouseup", function (e) {
+		panning = false;
+		if (tool_click) {
+			tool_click = false;
+			tool_release(e.clientX, e.clientY);
+			return;
+		}
+		if (!spawning_click) {
+			return;
+		}
+		spawning_click = false;
 		off_click(e.clientX, e.clientY);
 	});
+	canvas.addEventListener("contextmenu", function (e) {
+		e.preventDefault();
+	});
+	canvas.addEventListener("wheel", function (e) {
+		e.preventDefault();
+		zoom_camera(Math.pow(CAMERA_ZOOM_FACTOR, -Math.sign(e.deltaY)), e.clientX, e.clientY);
+	}, { passive: false });
 	let body_spawning_active = false;
 	canvas.addEventListener("touchstart", function (e) {
 		e.preventDefault();
+		if (tool() !== "spawn") {
+			update_mouse_position(e.touches[0].clientX, e.touches[0].clientY);
+			tool_press(e.touches[0].clientX, e.touches[0].clientY);
+			return;
+		}
 		body_spawning_active = !body_spawning_active;
 		if (body_spawning_active) {
 			on_click(e.touches[0].clientX, e.touches[0].clientY);
@@ -175,10 +382,29 @@ async function run() {
 		}
 	});
 
+	canvas.addEventListener("touchmove", function (e) {
+		e.preventDefault();
+		update_mouse_position(e.touches[0].clientX, e.touches[0].clientY);
+		tool_drag(e.touches[0].clientX, e.touches[0].clientY);
+	});
+	canvas.addEventListener("touchend", function (e) {
+		e.preventDefault();
+		if (e.changedTouches.length > 0) {
+			tool_release(e.changedTouches[0].clientX, e.changedTouches[0].clientY);
+		}
+	});
 
 	// Add mouse move listener
 	document.addEventListener("mousemove", function (event) {
 		update_mouse_position(event.clientX, event.clientY);
+		if (panning) {
+			pan_camera(event.clientX - pan_x, event.clientY - pan_y);
+			pan_x = event.clientX;
+			pan_y = event.clientY;
+		}
+		if (tool_click) {
+			tool_drag(event.clientX, event.clientY);
+		}
 	});
 
 	// Start the animation loop
@@ -187,6 +413,12 @@ async function run() {
 	function loop() {
 		ctx.clearRect(0, 0, canvas.width, canvas.height);
 		render_bodies();
+		// The camera mode also changes from panning, zooming and following
+		let mode_button = document.getElementById("camera-mode");
+		let mode = camera_mode();
+		if (mode_button.innerHTML !== mode) {
+			mode_button.innerHTML = mode;
+		}
 		frames += 1;
 		let now = Date.now();
 		let delta = now - last_time;
@@ -209,4 +441,24 @@ function cleanNumberInput(string) {
 		return "0";
 	}
 	return cleanString;
-}
\ No newline at end of file
+}
+// Shows the parameters of a loaded scene and its number of bodies
+function show_scene_parameters(parameters) {
+	document.getElementById("time-step").innerHTML = parameters.dt / TIME_STEP_MULTIPLIER;
+	document.getElementById("gravity").innerHTML = parameters.gravity;
+	document.getElementById("spawn-radius").innerHTML = parameters.spawn_radius;
+	document.getElementById("spawn-speed").innerHTML = parameters.spawn_speed / SPAWN_SPEED_MULTIPLIER;
+	document.getElementById("zoom").innerHTML = parameters.scale_multiplier;
+	document.getElementById("spawn-shape").innerHTML = parameters.spawn_shape ?? "square";
+	let spin = parameters.spawn_spin ?? 0;
+	document.getElementById("spawn-spin").innerHTML = Object.keys(SPAWN_SPINS).find((name) => SPAWN_SPINS[name] === spin) ?? spin;
+	document.getElementById("num-bodies").innerHTML = num_bodies();
+}
+
+// Copies the colours the draw buffer's colour indexes refer to from wasm
+function load_palette() {
+	body_colours.length = 0;
+	for (let i = 0; i < num_palette_colours(); i++) {
+		body_colours.push(palette_colour(i));
+	}
+}
@@ -1,9 +1,16 @@
+use density::{ColourMap, Scaling};
 use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
 
 mod constants;
-mod simulation;
+pub mod ffi;
+pub mod simulation;
 
+use crate::simulation::camera::CameraMode;
+use crate::simulation::colouring::ColourMode;
+use crate::simulation::spawner::SpawnShape;
+use crate::simulation::tools::Tool;
 use crate::simulation::Simulation;
 
 #[macro_use]
@@ -20,8 +27,12 @@ lazy_static! {
 #[wasm_bindgen]
 extern "C" {
     pub fn draw_body(x: f64, y: f64, color: &str, size: i32);
+    pub fn draw_body_buffer(buffer: &[f32]);
     pub fn draw_arrow(x1: f64, y1: f64, x2: f64, y2: f64, color: &str);
+    pub fn draw_image(pixels: Clamped<&[u8]>, width: u32, height: u32);
+    pub fn draw_trail(points: &[f64], color: &str);
     pub fn increase_num_bodies(num: usize);
+    pub fn decrease_num_bodies(num: usize);
     // for logging
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(s: &str);
@@ -57,6 +68,68 @@ pub fn set_dt(dt: f64) {
 pub fn set_gravity(gravity: f64) {
     BODIES.lock().unwrap().gravity = gravity;
 }
+// The scene as JSON, to be loaded again with import_state
+#[wasm_bindgen]
+pub fn export_state() -> Result<String, JsValue> {
+    BODIES
+        .lock()
+        .unwrap()
+        .export_state()
+        .map_err(|err| JsValue::from_str(&err))
+}
+// Replaces the scene with one from export_state, throwing if it can't be read
+#[wasm_bindgen]
+pub fn import_state(json: &str) -> Result<(), JsValue> {
+    BODIES
+        .lock()
+        .unwrap()
+        .import_state(json)
+        .map_err(|err| JsValue::from_str(&err))
+}
+// The scene as a short code for links, to be loaded again with import_scene_code
+#[wasm_bindgen]
+pub fn export_scene_code() -> Result<String, JsValue> {
+    BODIES
+        .lock()
+        .unwrap()
+        .export_scene_code()
+        .map_err(|err| JsValue::from_str(&err))
+}
+// Replaces the scene with one from export_scene_code, throwing if it can't be read
+#[wasm_bindgen]
+pub fn import_scene_code(code: &str) -> Result<(), JsValue> {
+    BODIES
+        .lock()
+        .unwrap()
+        .import_scene_code(code)
+        .map_err(|err| JsValue::from_str(&err))
+}
+#[wasm_bindgen]
+pub fn num_bodies() -> usize {
+    BODIES.lock().unwrap().num_bodies
+}
+#[wasm_bindgen]
+pub fn pause() {
+    BODIES.lock().unwrap().paused = true;
+}
+#[wasm_bindgen]
+pub fn resume() {
+    let mut simulation = BODIES.lock().unwrap();
+    simulation.paused = false;
+    simulation.step_requested = false;
+}
+// Pauses and advances by one step on the next frame
+#[wasm_bindgen]
+pub fn step_once() {
+    let mut simulation = BODIES.lock().unwrap();
+    simulation.paused = true;
+    simulation.step_requested = true;
+}
+// Runs forwards for a positive direction and backwards for a negative one
+#[wasm_bindgen]
+pub fn set_time_direction(direction: f64) {
+    BODIES.lock().unwrap().reversed = direction < 0.0;
+}
 #[wasm_bindgen]
 pub fn set_spawn_radius(spawn_radius: f64) {
     BODIES.lock().unwrap().spawner.spawn_radius = spawn_radius;
@@ -65,6 +138,28 @@ pub fn set_spawn_radius(spawn_radius: f64) {
 pub fn set_spawn_speed(g: f64) {
     BODIES.lock().unwrap().spawner.spawn_speed = g;
 }
+// "square", "disk", "ring", "line" or "plummer"
+#[wasm_bindgen]
+pub fn set_spawn_shape(name: &str) {
+    match SpawnShape::from_name(name) {
+        Some(shape) => BODIES.lock().unwrap().spawner.shape = shape,
+        None => log(&format!("Unknown spawn shape: {}", name)),
+    }
+}
+#[wasm_bindgen]
+pub fn spawn_shape() -> String {
+    BODIES.lock().unwrap().spawner.shape.name().to_string()
+}
+// 1 spins new bodies anticlockwise at the speed of a circular orbit, -1 clockwise and 0 not at all
+#[wasm_bindgen]
+pub fn set_spawn_spin(spin: f64) {
+    BODIES.lock().unwrap().spawner.spin = spin;
+}
+// The number of steps ahead the path of the bodies being spawned is drawn, 0 for none
+#[wasm_bindgen]
+pub fn set_spawn_preview_steps(steps: usize) {
+    BODIES.lock().unwrap().spawn_preview.steps = steps;
+}
 #[wasm_bindgen]
 pub fn update_mouse_position(x: f64, y: f64) {
     BODIES.lock().unwrap().spawner.current_mouse_x = x;
@@ -74,3 +169,213 @@ pub fn update_mouse_position(x: f64, y: f64) {
 pub fn set_scale_multiplier(scale_multiplier: f64) {
     BODIES.lock().unwrap().scale_multiplier = scale_multiplier;
 }
+#[wasm_bindgen]
+pub fn set_draw_density(draw_density: bool) {
+    BODIES.lock().unwrap().draw_density = draw_density;
+}
+#[wasm_bindgen]
+pub fn set_colour_map(name: &str) {
+    match ColourMap::from_name(name) {
+        Some(colour_map) => BODIES.lock().unwrap().colour_map = colour_map,
+        None => log(&format!("Unknown colour map: {}", name)),
+    }
+}
+#[wasm_bindgen]
+pub fn set_density_scaling(name: &str) {
+    match Scaling::from_name(name) {
+        Some(scaling) => BODIES.lock().unwrap().density_scaling = scaling,
+        None => log(&format!("Unknown density scaling: {}", name)),
+    }
+}
+#[wasm_bindgen]
+pub fn set_density_smoothing(density_smoothing: f64) {
+    BODIES.lock().unwrap().density_smoothing = density_smoothing;
+}
+#[wasm_bindgen]
+pub fn track_body(id: u64) {
+    BODIES.lock().unwrap().trails.track(id);
+}
+#[wasm_bindgen]
+pub fn untrack_body(id: u64) {
+    BODIES.lock().unwrap().trails.untrack(id);
+}
+#[wasm_bindgen]
+pub fn clear_tracked_bodies() {
+    BODIES.lock().unwrap().trails.clear();
+}
+// Tracks the body drawn closest to the given canvas position
+#[wasm_bindgen]
+pub fn track_nearest_body(x: f64, y: f64) {
+    let mut simulation = BODIES.lock().unwrap();
+    if let Some(index) = simulation.nearest_body(x, y) {
+        let id: u64 = simulation.ids[index];
+        simulation.trails.track(id);
+    }
+}
+// "spawn", "erase", "grab", "fling" or "place"
+#[wasm_bindgen]
+pub fn set_tool(name: &str) {
+    match Tool::from_name(name) {
+        Some(tool) => BODIES.lock().unwrap().tool = tool,
+        None => log(&format!("Unknown tool: {}", name)),
+    }
+}
+#[wasm_bindgen]
+pub fn tool() -> String {
+    BODIES.lock().unwrap().tool.name().to_string()
+}
+// The radius in canvas pixels of the bodies the erase, grab and fling tools act on
+#[wasm_bindgen]
+pub fn set_brush_radius(radius: f64) {
+    BODIES.lock().unwrap().brush_radius = radius;
+}
+// Pressing, dragging and releasing the erase, grab and fling tools at canvas positions
+#[wasm_bindgen]
+pub fn tool_press(x: f64, y: f64) {
+    BODIES.lock().unwrap().tool_press(x, y);
+}
+#[wasm_bindgen]
+pub fn tool_drag(x: f64, y: f64) {
+    BODIES.lock().unwrap().tool_drag(x, y);
+}
+#[wasm_bindgen]
+pub fn tool_release(x: f64, y: f64) {
+    BODIES.lock().unwrap().tool_release(x, y);
+}
+// The mass of bodies added with the place tool, e.g. 1000 for a black hole
+// worth 1000 normal bodies, and whether they are pinned in place
+#[wasm_bindgen]
+pub fn set_place_mass(mass: f64) {
+    if mass.is_finite() && mass > 0.0 {
+        BODIES.lock().unwrap().place_mass = mass;
+    }
+}
+#[wasm_bindgen]
+pub fn set_place_fixed(fixed: bool) {
+    BODIES.lock().unwrap().place_fixed = fixed;
+}
+// "auto", "manual" or "fixed", use follow_body to follow a body
+#[wasm_bindgen]
+pub fn set_camera_mode(name: &str) {
+    match CameraMode::from_name(name) {
+        Some(mode) => BODIES.lock().unwrap().camera.set_mode(mode),
+        None => log(&format!("Unknown camera mode: {}", name)),
+    }
+}
+#[wasm_bindgen]
+pub fn camera_mode() -> String {
+    BODIES.lock().unwrap().camera.mode.name().to_string()
+}
+#[wasm_bindgen]
+pub fn follow_body(id: u64) {
+    BODIES.lock().unwrap().camera.follow(id);
+}
+// Follows the body drawn closest to the given canvas position
+#[wasm_bindgen]
+pub fn follow_nearest_body(x: f64, y: f64) {
+    let mut simulation = BODIES.lock().unwrap();
+    if let Some(index) = simulation.nearest_body(x, y) {
+        let id: u64 = simulation.ids[index];
+        simulation.camera.follow(id);
+    }
+}
+// Moves the view by a distance in canvas pixels
+#[wasm_bindgen]
+pub fn pan_camera(dx: f64, dy: f64) {
+    BODIES.lock().unwrap().camera.pan(dx, dy);
+}
+// Zooms by factor about the given canvas position
+#[wasm_bindgen]
+pub fn zoom_camera(factor: f64, x: f64, y: f64) {
+    let mut simulation = BODIES.lock().unwrap();
+    let offset: (f64, f64) = (
+        x - simulation.canvas_half_width,
+        y - simulation.canvas_half_height,
+    );
+    simulation.camera.zoom(factor, offset);
+}
+// Shows the given width of the simulation around (x, y)
+#[wasm_bindgen]
+pub fn set_fixed_camera(x: f64, y: f64, width: f64) {
+    let mut simulation = BODIES.lock().unwrap();
+    if width > 0.0 {
+        let scale: f64 = simulation.canvas_width / width;
+        simulation.camera.fix((x, y), scale);
+    }
+}
+#[wasm_bindgen]
+pub fn set_camera_smoothing(smoothing: f64) {
+    if smoothing.is_finite() {
+        BODIES.lock().unwrap().camera.smoothing = smoothing;
+    }
+}
+// Removes bodies further than max_distance from the centre of mass, or none if it is not positive
+#[wasm_bindgen]
+pub fn set_removal_distance(max_distance: f64) {
+    BODIES.lock().unwrap().removal.max_distance = (max_distance > 0.0).then_some(max_distance);
+}
+#[wasm_bindgen]
+pub fn set_remove_unbound(remove_unbound: bool) {
+    BODIES.lock().unwrap().removal.remove_unbound = remove_unbound;
+}
+// Lets the removal policy remove pinned bodies too
+#[wasm_bindgen]
+pub fn set_remove_pinned(remove_pinned: bool) {
+    BODIES.lock().unwrap().removal.remove_pinned = remove_pinned;
+}
+// Draws bodies from the draw buffer with one JS call per frame instead of one per body
+#[wasm_bindgen]
+pub fn set_use_draw_buffer(use_draw_buffer: bool) {
+    BODIES.lock().unwrap().use_draw_buffer = use_draw_buffer;
+}
+// The draw buffer in wasm memory holds (canvas x, canvas y, colour index, size) as f32 for
+// each body drawn in the last frame. The pointer is only valid until the next render_bodies
+// call, and views of wasm memory must be recreated if it grows.
+#[wasm_bindgen]
+pub fn draw_buffer_ptr() -> *const f32 {
+    BODIES.lock().unwrap().draw_buffer.as_ptr()
+}
+// The number of f32 values in the draw buffer
+#[wasm_bindgen]
+pub fn draw_buffer_len() -> usize {
+    BODIES.lock().unwrap().draw_buffer.len()
+}
+// The colours that the draw buffer's colour indexes refer to, which change
+// with the colour mode and gradient
+#[wasm_bindgen]
+pub fn num_palette_colours() -> usize {
+    BODIES.lock().unwrap().colouring.palette().len()
+}
+#[wasm_bindgen]
+pub fn palette_colour(index: usize) -> String {
+    let simulation = BODIES.lock().unwrap();
+    let palette: &[String] = simulation.colouring.palette();
+    palette[index % palette.len()].clone()
+}
+// "star", "speed", "energy", "acceleration" or "batch"
+#[wasm_bindgen]
+pub fn set_colour_mode(name: &str) {
+    match ColourMode::from_name(name) {
+        Some(mode) => BODIES.lock().unwrap().colouring.set_mode(mode),
+        None => log(&format!("Unknown colour mode: {}", name)),
+    }
+}
+#[wasm_bindgen]
+pub fn colour_mode() -> String {
+    BODIES.lock().unwrap().colouring.mode().name().to_string()
+}
+// Sets the gradient the colour modes use from evenly spaced colours given as
+// [r0, g0, b0, r1, ...], throwing if there are fewer than two colours
+#[wasm_bindgen]
+pub fn set_colour_gradient(rgb: &[u8]) -> Result<(), JsValue> {
+    BODIES
+        .lock()
+        .unwrap()
+        .colouring
+        .set_gradient(rgb)
+        .map_err(|err| JsValue::from_str(&err))
+}
+#[wasm_bindgen]
+pub fn set_trail_length(length: usize) {
+    BODIES.lock().unwrap().trails.set_max_length(length);
+}
@@ -1,19 +1,34 @@
 use rand::{rngs::ThreadRng, Rng};
 
-mod bh_tree;
-mod calc_acceleration;
+pub mod bh_tree;
+pub mod calc_acceleration;
+pub mod camera;
+pub mod colouring;
 mod energy_conservation;
-mod spawner;
+pub mod preview;
+pub mod removal;
+pub mod scene_code;
+pub mod spawner;
+pub mod state;
+pub mod tools;
+mod trails;
 
 use crate::constants::{
-    BODY_DRAW_SIZE, BODY_DRAW_SIZE_MOBILE, ROOT_NODE_INDEX, START_BOX_SIZE, STAR_COLOURS,
-    STAR_COLOURS_LEN,
+    BODY_DRAW_SIZE, BODY_DRAW_SIZE_MOBILE, DRAW_BUFFER_STRIDE, PLACE_MASS, ROOT_NODE_INDEX,
+    START_BOX_SIZE, TOOL_BRUSH_RADIUS,
 };
-use crate::{draw_body, log, log_u32};
+use crate::{decrease_num_bodies, draw_body, draw_body_buffer, draw_image, log};
+use density::{ColourMap, DensityMap, Scaling};
+use wasm_bindgen::Clamped;
 
 use self::bh_tree::Tree;
-use self::energy_conservation::log_energy;
+use self::camera::{Camera, CameraMode};
+use self::colouring::Colouring;
+use self::preview::SpawnPreview;
+use self::removal::{RemovalPolicy, RemovalReason};
 use self::spawner::Spawner;
+use self::tools::Tool;
+use self::trails::Trails;
 
 // The bodies struct is a Struct of Arrays (SoA) implementation of the bodies
 pub struct Simulation {
@@ -24,6 +39,16 @@ pub struct Simulation {
     pub vy: Vec<f64>,
     pub ax: Vec<f64>,
     pub ay: Vec<f64>,
+    // Normal bodies have a mass of 1
+    pub masses: Vec<f64>,
+    // Pinned bodies never move, but still attract the others
+    pub fixed: Vec<bool>,
+    // The spawn batch each body was added in, and the batch new bodies join
+    pub batches: Vec<u64>,
+    pub batch: u64,
+    // Identifies each body for its whole life, whatever its index
+    pub ids: Vec<u64>,
+    pub next_id: u64,
     pub canvas_width: f64,
     pub canvas_height: f64,
     pub canvas_half_width: f64,
@@ -32,12 +57,45 @@ pub struct Simulation {
     pub com_distances: Vec<f64>,
     pub is_mobile: bool,
     pub spawner: Spawner,
+    pub spawn_preview: SpawnPreview,
     pub clicked: bool,
+    // The centre and pixels per unit length of the view, set by the camera each frame
     pub com: (f64, f64),
     pub scale: f64,
+    pub camera: Camera,
     pub scale_multiplier: f64,
     pub dt: f64,
     pub gravity: f64,
+    // Stops update() from advancing the bodies, except for requested single steps
+    pub paused: bool,
+    pub step_requested: bool,
+    // Runs the simulation backwards
+    pub reversed: bool,
+    pub draw_density: bool,
+    pub colour_map: ColourMap,
+    pub density_scaling: Scaling,
+    pub density_smoothing: f64,
+    pub trails: Trails,
+    // How bodies are coloured when drawn
+    pub colouring: Colouring,
+    pub removal: RemovalPolicy,
+    // Whether bodies are drawn from draw_buffer with one call instead of one call each
+    pub use_draw_buffer: bool,
+    // The bodies drawn in the last frame, DRAW_BUFFER_STRIDE floats each
+    pub draw_buffer: Vec<f32>,
+    // What pressing on the canvas does, with the radius in canvas pixels of
+    // the bodies the erase, grab and fling tools act on
+    pub tool: Tool,
+    pub brush_radius: f64,
+    // The mass of bodies added with the place tool, and whether they are pinned
+    pub place_mass: f64,
+    pub place_fixed: bool,
+    // Where the held tool was pressed and last moved to, and the indexes of the bodies it holds
+    tool_press_position: Option<(f64, f64)>,
+    tool_last_position: (f64, f64),
+    selected: Vec<usize>,
+    density_map: DensityMap,
+    density_pixels: Vec<u8>,
 }
 impl Simulation {
     pub fn new_empty() -> Simulation {
@@ -49,6 +107,12 @@ impl Simulation {
             vy: Vec::new(),
             ax: Vec::new(),
             ay: Vec::new(),
+            masses: Vec::new(),
+            fixed: Vec::new(),
+            batches: Vec::new(),
+            batch: 0,
+            ids: Vec::new(),
+            next_id: 0,
             canvas_width: 0.0,
             canvas_height: 0.0,
             canvas_half_width: 0.0,
@@ -57,12 +121,35 @@ impl Simulation {
             com_distances: Vec::new(),
             is_mobile: false,
             spawner: Spawner::new_empty(),
+            spawn_preview: SpawnPreview::new(),
             clicked: false,
             com: (0.0, 0.0),
             scale: 1.0,
+            camera: Camera::new(),
             scale_multiplier: 1.0,
             dt: 0.0,
             gravity: 0.0,
+            paused: false,
+            step_requested: false,
+            reversed: false,
+            draw_density: false,
+            colour_map: ColourMap::Inferno,
+            density_scaling: Scaling::Log,
+            density_smoothing: 1.0,
+            trails: Trails::new(),
+            colouring: Colouring::new(),
+            removal: RemovalPolicy::default(),
+            use_draw_buffer: false,
+            draw_buffer: Vec::new(),
+            tool: Tool::Spawn,
+            brush_radius: TOOL_BRUSH_RADIUS,
+            place_mass: PLACE_MASS,
+            place_fixed: false,
+            tool_press_position: None,
+            tool_last_position: (0.0, 0.0),
+            selected: Vec::new(),
+            density_map: DensityMap::new(0, 0),
+            density_pixels: Vec::new(),
         }
     }
     pub fn create(&mut self, num: usize, canvas_width: f64, canvas_height: f64, is_mobile: bool) {
@@ -73,13 +160,20 @@ impl Simulation {
         self.canvas_half_height = canvas_height / 2.0;
         self.num_bodies = num;
         self.spawner.create(is_mobile, canvas_width, canvas_height);
+        self.density_map
+            .resize(canvas_width as usize, canvas_height as usize);
+        self.density_pixels = vec![0; canvas_width as usize * canvas_height as usize * 4];
         self.x.reserve(num);
         self.y.reserve(num);
         self.vx.reserve(num);
         self.vy.reserve(num);
         self.ax.reserve(num);
         self.ay.reserve(num);
+        self.masses.reserve(num);
+        self.fixed.reserve(num);
+        self.batches.reserve(num);
         self.com_distances.reserve(num);
+        self.ids.reserve(num);
         let mut rng: ThreadRng = rand::thread_rng();
         for _ in 0..num {
             self.x.push(rng.gen_range(-START_BOX_SIZE..START_BOX_SIZE));
@@ -88,7 +182,12 @@ impl Simulation {
             self.vy.push(0.0);
             self.ax.push(0.0);
             self.ay.push(0.0);
+            self.masses.push(1.0);
+            self.fixed.push(false);
+            self.batches.push(self.batch);
             self.com_distances.push(0.0);
+            self.ids.push(self.next_id);
+            self.next_id += 1;
         }
         // Set the half-width of the root node to the largest dimension of the system
         self.bh_tree.root_half_width = self.canvas_width.max(self.canvas_height) / 2.0;
@@ -102,6 +201,8 @@ impl Simulation {
     }
     pub fn off_click(&mut self, x: f64, y: f64) {
         self.clicked = false;
+        self.spawn_preview.clear();
+        self.start_batch();
         self.spawner.add_spawned_bodies_to_simulation(
             x,
             y,
@@ -111,25 +212,95 @@ impl Simulation {
             &mut self.vy,
             &mut self.ax,
             &mut self.ay,
+            &mut self.masses,
+            &mut self.fixed,
+            &mut self.batches,
+            self.batch,
             &mut self.com_distances,
+            &mut self.ids,
+            &mut self.next_id,
             &mut self.num_bodies,
             &mut self.bh_tree,
+            self.gravity,
         );
     }
 
     pub fn draw(&mut self) {
-        self.com = self.get_com();
-        let percentile: f64 = self.get_99th_percentile(self.com);
-        self.scale = self.scale_multiplier * self.canvas_width / (2.0 * percentile);
+        self.draw_buffer.clear();
+        // Keep the last view if every body has been removed
+        if self.num_bodies == 0 {
+            if self.clicked {
+                self.draw_spawn_preview();
+                self.spawner.draw_spawned_bodies(self.com, self.scale);
+            }
+            self.draw_tool();
+            return;
+        }
+        let com: (f64, f64) = self.get_com();
+        let percentile: f64 = self.get_99th_percentile(com);
+        let auto_scale: f64 = self.scale_multiplier * self.canvas_width / (2.0 * percentile);
+        let followed: Option<(f64, f64)> = match self.camera.mode {
+            CameraMode::Follow(id) => self
+                .ids
+                .iter()
+                .position(|&body_id| body_id == id)
+                .map(|i| (self.x[i], self.y[i])),
+            _ => None,
+        };
+        (self.com, self.scale) = self.camera.update(com, auto_scale, followed);
+        if self.draw_density {
+            self.draw_density_map();
+        } else {
+            self.draw_bodies();
+        }
+
+        self.trails.draw(
+            self.com,
+            self.scale,
+            self.canvas_half_width,
+            self.canvas_half_height,
+        );
+
+        if self.clicked {
+            self.draw_spawn_preview();
+            self.spawner.draw_spawned_bodies(self.com, self.scale);
+        }
+        self.draw_tool();
+
+        // print percentile * 2
+        // log(&format!("Width (AU): {}", percentile * 2.0));
+    }
+
+    fn draw_spawn_preview(&mut self) {
+        self.spawn_preview.draw(
+            self.com,
+            self.scale,
+            self.canvas_half_width,
+            self.canvas_half_height,
+        );
+    }
+
+    fn draw_bodies(&mut self) {
+        let body_draw_size = if self.is_mobile {
+            BODY_DRAW_SIZE_MOBILE
+        } else {
+            BODY_DRAW_SIZE
+        };
+        self.colour_bodies();
+        if self.use_draw_buffer {
+            self.draw_buffer
+                .reserve(self.num_bodies * DRAW_BUFFER_STRIDE);
+        }
         for i in 0..self.num_bodies {
             // calculate the canvas position of the body such that the centre of mass is at the centre of the canvas (canvas_half_width, canvas_half_height) and 99% of the bodies are inside the canvas
             let canvas_x: f64 = (self.x[i] - self.com.0) * self.scale + self.canvas_half_width;
             let canvas_y: f64 = (self.y[i] - self.com.1) * self.scale + self.canvas_half_height;
-            let color: &str = STAR_COLOURS[i % STAR_COLOURS_LEN];
-            let body_draw_size = if self.is_mobile {
-                BODY_DRAW_SIZE_MOBILE
+            let colour_index: usize = self.colouring.index(i);
+            // heavier bodies are drawn larger, e.g. 4 times the size for a mass of 1000
+            let size: i32 = if self.masses[i] > 1.0 {
+                (body_draw_size as f64 * (1.0 + self.masses[i].log10())).round() as i32
             } else {
-                BODY_DRAW_SIZE
+                body_draw_size
             };
             // if inside the canvas, draw the body
             if canvas_x >= 0.0
@@ -137,27 +308,151 @@ impl Simulation {
                 && canvas_y >= 0.0
                 && canvas_y <= self.canvas_height
             {
-                draw_body(canvas_x, canvas_y, color, body_draw_size);
+                if self.use_draw_buffer {
+                    self.draw_buffer.extend_from_slice(&[
+                        canvas_x as f32,
+                        canvas_y as f32,
+                        colour_index as f32,
+                        size as f32,
+                    ]);
+                } else {
+                    draw_body(
+                        canvas_x,
+                        canvas_y,
+                        &self.colouring.palette()[colour_index],
+                        size,
+                    );
+                }
             }
         }
-
-        if self.clicked {
-            self.spawner.draw_spawned_bodies(self.com, self.scale);
+        // JS sees the buffer in wasm memory, so this is one call without a copy
+        if self.use_draw_buffer {
+            draw_body_buffer(&self.draw_buffer);
         }
+    }
 
-        // print percentile * 2
-        // log(&format!("Width (AU): {}", percentile * 2.0));
+    // Draws the bodies as a density map covering the whole canvas
+    fn draw_density_map(&mut self) {
+        self.density_map
+            .accumulate(&self.x, &self.y, self.com, self.scale);
+        self.density_map.smooth(self.density_smoothing);
+        self.density_map.to_rgba(
+            self.density_scaling,
+            self.colour_map,
+            &mut self.density_pixels,
+        );
+        draw_image(
+            Clamped(&self.density_pixels),
+            self.density_map.width as u32,
+            self.density_map.height as u32,
+        );
     }
 
     pub fn update(&mut self) {
         // if the mouse is clicked, spawn bodies
         if self.clicked {
             self.spawner.spawn_body(self.com, self.scale);
+            self.predict_spawn_path();
             return;
         }
+        // bodies held by a tool stay where the tool puts them
+        if self.is_tool_held() {
+            return;
+        }
+        // while paused, only advance when a single step was asked for
+        if self.paused {
+            if !self.step_requested {
+                return;
+            }
+            self.step_requested = false;
+        }
+        self.step();
+        let removed: Vec<(u64, RemovalReason)> = self.remove_bodies();
+        if !removed.is_empty() {
+            for (id, reason) in &removed {
+                log(&format!("Removed body {} ({})", id, reason.name()));
+            }
+            decrease_num_bodies(removed.len());
+        }
+        self.trails.record(&self.ids, &self.x, &self.y);
+
+        // log enegy
+        // log_energy(&self.x, &self.y, &self.vx, &self.vy, self.num_bodies);
+    }
+
+    // Advances every body by one time step, or goes back one step if time is
+    // reversed. The tree root must already cover the bodies, which draw() or
+    // fit_tree_root() take care of.
+    pub fn step(&mut self) {
+        if self.reversed {
+            self.step_back();
+            return;
+        }
+        self.calc_accelerations();
+        // Update the velocity and position for each body that isn't pinned
+        for i in 0..self.num_bodies {
+            if self.fixed[i] {
+                continue;
+            }
+            self.vx[i] += self.ax[i] * self.gravity * self.dt;
+            self.vy[i] += self.ay[i] * self.gravity * self.dt;
+            self.x[i] += self.vx[i] * self.dt;
+            self.y[i] += self.vy[i] * self.dt;
+        }
+    }
+
+    // Undoes a forward step by applying its updates in the opposite order, so
+    // running backwards retraces the forward trajectory
+    fn step_back(&mut self) {
+        for i in 0..self.num_bodies {
+            if self.fixed[i] {
+                continue;
+            }
+            self.x[i] -= self.vx[i] * self.dt;
+            self.y[i] -= self.vy[i] * self.dt;
+        }
+        // The forward step built its tree around these positions with the root drawing uses
+        self.fit_tree_root();
+        self.calc_accelerations();
+        for i in 0..self.num_bodies {
+            if self.fixed[i] {
+                continue;
+            }
+            self.vx[i] -= self.ax[i] * self.gravity * self.dt;
+            self.vy[i] -= self.ay[i] * self.gravity * self.dt;
+        }
+    }
+
+    // Predicts the path of the bodies being spawned through the field of the
+    // others, in the direction time is running
+    fn predict_spawn_path(&mut self) {
+        let start: (f64, f64) = match self.spawner.spawned_centre() {
+            Some(centre) => centre,
+            None => {
+                self.spawn_preview.clear();
+                return;
+            }
+        };
+        let velocity: (f64, f64) = self
+            .spawner
+            .drag_velocity(self.spawner.current_mouse_x, self.spawner.current_mouse_y);
+        let bh_tree: Option<&Tree> = if self.num_bodies > 0 {
+            self.fit_tree_root();
+            self.bh_tree
+                .construct(&self.x, &self.y, &self.masses, self.num_bodies);
+            Some(&self.bh_tree)
+        } else {
+            None
+        };
+        let dt: f64 = if self.reversed { -self.dt } else { self.dt };
+        self.spawn_preview
+            .predict(bh_tree, start, velocity, self.gravity, dt);
+    }
 
+    fn calc_accelerations(&mut self) {
         // Re-construct the Barnes-Hut tree
-        self.bh_tree.construct(&self.x, &self.y, self.num_bodies);
+        self.bh_tree
+            .construct(&self.x, &self.y, &self.masses, self.num_bodies);
         // Compute the acceleration for each body
         for i in 0..self.num_bodies {
             let (new_ax, new_ay): (f64, f64) = calc_acceleration::add_node_acceleration(
@@ -169,29 +464,135 @@ impl Simulation {
             self.ax[i] = new_ax;
             self.ay[i] = new_ay;
         }
+    }
+
+    // Removes the bodies selected by the removal policy, returning their ids
+    // and why they were removed
+    pub fn remove_bodies(&mut self) -> Vec<(u64, RemovalReason)> {
+        let removals: Vec<(usize, RemovalReason)> = removal::find_removals(
+            &self.x,
+            &self.y,
+            &self.vx,
+            &self.vy,
+            &self.masses,
+            &self.fixed,
+            self.gravity,
+            &self.removal,
+        );
+        if removals.is_empty() {
+            return Vec::new();
+        }
+        self.remove_at(&removals)
+    }
 
-        // Update the velocity and position for each body
+    // Removes the bodies at the given (ordered) indexes, returning their ids
+    // with the reasons given
+    fn remove_at(&mut self, removals: &[(usize, RemovalReason)]) -> Vec<(u64, RemovalReason)> {
+        let removed: Vec<(u64, RemovalReason)> = removals
+            .iter()
+            .map(|&(index, reason)| (self.ids[index], reason))
+            .collect();
+        for values in [
+            &mut self.x,
+            &mut self.y,
+            &mut self.vx,
+            &mut self.vy,
+            &mut self.ax,
+            &mut self.ay,
+            &mut self.masses,
+            &mut self.com_distances,
+        ] {
+            removal::remove_indexes(values, removals);
+        }
+        removal::remove_indexes(&mut self.fixed, removals);
+        removal::remove_indexes(&mut self.batches, removals);
+        removal::remove_indexes(&mut self.ids, removals);
+        self.num_bodies = self.ids.len();
+        for (id, _) in &removed {
+            self.trails.untrack(*id);
+        }
+        removed
+    }
+
+    // Finds the body drawn closest to the given canvas position
+    pub fn nearest_body(&self, canvas_x: f64, canvas_y: f64) -> Option<usize> {
+        // Convert to simulation coordinates with the view used for drawing
+        let x: f64 = (canvas_x - self.canvas_half_width) / self.scale + self.com.0;
+        let y: f64 = (canvas_y - self.canvas_half_height) / self.scale + self.com.1;
+        let mut nearest: Option<(usize, f64)> = None;
         for i in 0..self.num_bodies {
-            self.vx[i] += self.ax[i] * self.gravity * self.dt;
-            self.vy[i] += self.ay[i] * self.gravity * self.dt;
-            self.x[i] += self.vx[i] * self.dt;
-            self.y[i] += self.vy[i] * self.dt;
+            let distance: f64 = (self.x[i] - x).powi(2) + (self.y[i] - y).powi(2);
+            if nearest.is_none_or(|(_, nearest_distance)| distance < nearest_distance) {
+                nearest = Some((i, distance));
+            }
         }
+        nearest.map(|(i, _)| i)
+    }
 
-        // log enegy
-        // log_energy(&self.x, &self.y, &self.vx, &self.vy, self.num_bodies);
+    // Makes bodies added from now on a new spawn batch
+    pub fn start_batch(&mut self) {
+        self.batch += 1;
+    }
+
+    // Adds a body at the end of the arrays with the next id, returning the id
+    pub fn add_body(&mut self, x: f64, y: f64, vx: f64, vy: f64) -> u64 {
+        self.add_massive_body(x, y, vx, vy, 1.0, false)
+    }
+    // Adds a body with the given mass, pinned in place if fixed, returning its id
+    pub fn add_massive_body(
+        &mut self,
+        x: f64,
+        y: f64,
+        vx: f64,
+        vy: f64,
+        mass: f64,
+        fixed: bool,
+    ) -> u64 {
+        self.x.push(x);
+        self.y.push(y);
+        self.vx.push(vx);
+        self.vy.push(vy);
+        self.ax.push(0.0);
+        self.ay.push(0.0);
+        self.masses.push(mass);
+        self.fixed.push(fixed);
+        self.batches.push(self.batch);
+        self.com_distances.push(0.0);
+        self.ids.push(self.next_id);
+        self.next_id += 1;
+        self.num_bodies += 1;
+        self.next_id - 1
+    }
+
+    // Centres the tree root on the centre of mass with room for every body,
+    // the same way drawing does, for simulations that are stepped without drawing
+    pub fn fit_tree_root(&mut self) {
+        if self.num_bodies == 0 {
+            return;
+        }
+        let com: (f64, f64) = self.get_com();
+        let mut max_distance: f64 = 0.0;
+        for i in 0..self.num_bodies {
+            let dx: f64 = self.x[i] - com.0;
+            let dy: f64 = self.y[i] - com.1;
+            max_distance = max_distance.max((dx * dx + dy * dy).sqrt());
+        }
+        self.bh_tree.root_centre = com;
+        self.bh_tree.root_half_width = max_distance * 2.0;
     }
 
     // HELPERS
     fn get_com(&self) -> (f64, f64) {
         let mut com_x: f64 = 0.0;
         let mut com_y: f64 = 0.0;
+        let mut total_mass: f64 = 0.0;
         for i in 0..self.num_bodies {
-            com_x += self.x[i];
-            com_y += self.y[i];
+            com_x += self.x[i] * self.masses[i];
+            com_y += self.y[i] * self.masses[i];
+            total_mass += self.masses[i];
         }
-        com_x /= self.num_bodies as f64;
-        com_y /= self.num_bodies as f64;
+        com_x /= total_mass;
+        com_y /= total_mass;
         (com_x, com_y)
     }
     // get the 99th percentile of the distance from the centre of mass
@@ -12,8 +12,7 @@ pub struct Tree {
 }
 impl Tree {
     pub fn new() -> Tree {
-        let mut nodes: Vec<Node> = Vec::with_capacity(1);
-        nodes.push(Node::new());
+        let nodes: Vec<Node> = vec![Node::new()];
         Tree {
             nodes,
             num_nodes: 1,
@@ -23,3 +22,8 @@ impl Tree {
         }
     }
 }
+impl Default for Tree {
+    fn default() -> Self {
+        Tree::new()
+    }
+}
@@ -5,6 +5,7 @@ use super::{nodes::NodeDesc, Node, Tree};
 struct Insert {
     body_x: f64,
     body_y: f64,
+    body_mass: f64,
     target_node: NodeDesc,
 }
 impl Insert {
@@ -12,6 +13,7 @@ impl Insert {
         Insert {
             body_x: self.body_x,
             body_y: self.body_y,
+            body_mass: self.body_mass,
             target_node: self.target_node.clone(),
         }
     }
@@ -30,7 +32,8 @@ impl Tree {
     }
     fn zero_node(&mut self, node_index: usize) {
         self.nodes[node_index].centre_of_mass = (0.0, 0.0);
-        self.nodes[node_index].mass = 0;
+        self.nodes[node_index].mass = 0.0;
+        self.nodes[node_index].num_bodies = 0;
         self.nodes[node_index].top_left = 0;
         self.nodes[node_index].top_right = 0;
         self.nodes[node_index].bottom_left = 0;
@@ -134,7 +137,7 @@ impl Tree {
         }
         child_node_desc
     }
-    pub fn construct(&mut self, x: &Vec<f64>, y: &Vec<f64>, num_bodies: usize) {
+    pub fn construct(&mut self, x: &[f64], y: &[f64], masses: &[f64], num_bodies: usize) {
         // Initialise the root node
         self.zero_node(ROOT_NODE_INDEX);
         self.num_nodes = 1;
@@ -152,25 +155,26 @@ impl Tree {
             let insert: Insert = Insert {
                 body_x: x[i],
                 body_y: y[i],
+                body_mass: masses[i],
                 target_node: root_node_desc.clone(),
             };
             // Push insert onto stack
             insert_stack.push(insert);
         }
         // Process insert stack
-        while !insert_stack.is_empty() {
-            // Pop insert from stack
-            let insert: Insert = insert_stack.pop().unwrap();
-            // Get the x and y coordinates of the body
-            let (body_x, body_y): (f64, f64) = (insert.body_x, insert.body_y);
+        while let Some(insert) = insert_stack.pop() {
+            // Get the x and y coordinates and mass of the body
+            let (body_x, body_y, body_mass): (f64, f64, f64) =
+                (insert.body_x, insert.body_y, insert.body_mass);
             // Create reference to the node
             let target_node: &Node = &self.nodes[insert.target_node.index];
             // Check if the node is a leaf
             if target_node.is_leaf() {
                 // Node is a leaf
-                if target_node.mass == 0 {
+                if target_node.num_bodies == 0 {
                     // Node is an empty leaf: add the particle
-                    self.nodes[insert.target_node.index].mass = 1;
+                    self.nodes[insert.target_node.index].mass = body_mass;
+                    self.nodes[insert.target_node.index].num_bodies = 1;
                     self.nodes[insert.target_node.index].centre_of_mass = (body_x, body_y);
                 } else {
                     // Node is an occupied leaf: split the node and add the existing and new particles
@@ -179,11 +183,13 @@ impl Tree {
                         self.nodes[insert.target_node.index].centre_of_mass.0;
                     let quadrant_body_y: f64 =
                         self.nodes[insert.target_node.index].centre_of_mass.1;
+                    let quadrant_body_mass: f64 = self.nodes[insert.target_node.index].mass;
                     let child_desc: NodeDesc =
                         self.get_child_node(quadrant_body_x, quadrant_body_y, &insert.target_node);
                     let existing_insert_as_child: Insert = Insert {
                         body_x: quadrant_body_x,
                         body_y: quadrant_body_y,
+                        body_mass: quadrant_body_mass,
                         target_node: child_desc,
                     };
                     insert_stack.push(existing_insert_as_child);
@@ -192,22 +198,20 @@ impl Tree {
                 }
             } else {
                 // Node is not a leaf: update the mass and centre of mass of the node
-                self.nodes[insert.target_node.index].centre_of_mass = (
-                    (self.nodes[insert.target_node.index].centre_of_mass.0
-                        * (self.nodes[insert.target_node.index].mass as f64)
-                        + body_x)
-                        / (self.nodes[insert.target_node.index].mass + 1) as f64,
-                    (self.nodes[insert.target_node.index].centre_of_mass.1
-                        * (self.nodes[insert.target_node.index].mass as f64)
-                        + body_y)
-                        / (self.nodes[insert.target_node.index].mass + 1) as f64,
+                let node: &mut Node = &mut self.nodes[insert.target_node.index];
+                let mass: f64 = node.mass + body_mass;
+                node.centre_of_mass = (
+                    (node.centre_of_mass.0 * node.mass + body_x * body_mass) / mass,
+                    (node.centre_of_mass.1 * node.mass + body_y * body_mass) / mass,
                 );
-                self.nodes[insert.target_node.index].mass += 1;
+                node.mass = mass;
+                node.num_bodies += 1;
                 // Add the particle to the appropriate child
                 let child_desc: NodeDesc = self.get_child_node(body_x, body_y, &insert.target_node);
                 let insert_as_child: Insert = Insert {
-                    body_x: body_x,
-                    body_y: body_y,
+                    body_x,
+                    body_y,
+                    body_mass,
                     target_node: child_desc,
                 };
                 insert_stack.push(insert_as_child);
@@ -1,6 +1,8 @@
 pub struct Node {
     pub centre_of_mass: (f64, f64),
-    pub mass: usize,
+    pub mass: f64,
+    // The number of bodies in the node, 1 for a leaf holding a body
+    pub num_bodies: usize,
     pub top_left: usize,
     pub top_right: usize,
     pub bottom_left: usize,
@@ -10,7 +12,8 @@ impl Node {
     pub fn new() -> Node {
         Node {
             centre_of_mass: (0.0, 0.0),
-            mass: 0,
+            mass: 0.0,
+            num_bodies: 0,
             top_left: 0,
             top_right: 0,
             bottom_left: 0,
@@ -15,18 +15,18 @@ pub fn add_node_acceleration(
     let dy: f64 = bh_tree.nodes[node_index].centre_of_mass.1 - body_y;
     let d: f64 = (dx * dx + dy * dy).sqrt();
     // If the node is a leaf, add the acceleration
-    if bh_tree.nodes[node_index].mass == 1 {
-        // Calculate and add the acceleration (mass is 1)
-        acceleration.0 += dx / (d * d * d + SOFTENING);
-        acceleration.1 += dy / (d * d * d + SOFTENING);
+    if bh_tree.nodes[node_index].num_bodies == 1 {
+        // Calculate and add the acceleration of the body
+        acceleration.0 += bh_tree.nodes[node_index].mass * dx / (d * d * d + SOFTENING);
+        acceleration.1 += bh_tree.nodes[node_index].mass * dy / (d * d * d + SOFTENING);
     }
     // If the node is not a leaf, check if the node is far enough to take its centre of mass
     else {
         // Check the s/d ratio for the node
         if s / d < THETA {
-            // Calculate and add the acceleration (mass is >1)
-            acceleration.0 += bh_tree.nodes[node_index].mass as f64 * dx / (d * d * d + SOFTENING);
-            acceleration.1 += bh_tree.nodes[node_index].mass as f64 * dy / (d * d * d + SOFTENING);
+            // Calculate and add the acceleration of the node's bodies
+            acceleration.0 += bh_tree.nodes[node_index].mass * dx / (d * d * d + SOFTENING);
+            acceleration.1 += bh_tree.nodes[node_index].mass * dy / (d * d * d + SOFTENING);
         } else {
             // Recursively calculate the acceleration
             let new_s = s / 2.0;
@@ -0,0 +1,170 @@
+use crate::constants::CAMERA_SMOOTHING;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    // Follows the centre of mass and scales to fit most of the bodies
+    Auto,
+    // Panned and zoomed by the user
+    Manual,
+    // Follows the body with the given id at the zoom it had when selected
+    Follow(u64),
+    // Stays where it is
+    Fixed,
+}
+impl CameraMode {
+    pub fn from_name(name: &str) -> Option<CameraMode> {
+        match name {
+            "auto" => Some(CameraMode::Auto),
+            "manual" => Some(CameraMode::Manual),
+            "fixed" => Some(CameraMode::Fixed),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Auto => "auto",
+            CameraMode::Manual => "manual",
+            CameraMode::Follow(_) => "follow",
+            CameraMode::Fixed => "fixed",
+        }
+    }
+}
+
+// Decides which part of the simulation is drawn. The view eases towards the
+// mode's target each frame so it doesn't jump when the target changes, e.g.
+// when a body is ejected and the auto scale shrinks.
+pub struct Camera {
+    pub mode: CameraMode,
+    // Fraction of the way the view moves towards the target each frame, 1 for no easing
+    pub smoothing: f64,
+    // The centre and pixels per unit length that the view is moving towards
+    target_centre: (f64, f64),
+    target_scale: f64,
+    // The view drawn in the last frame
+    centre: (f64, f64),
+    scale: f64,
+    has_view: bool,
+}
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            mode: CameraMode::Auto,
+            smoothing: CAMERA_SMOOTHING,
+            target_centre: (0.0, 0.0),
+            target_scale: 1.0,
+            centre: (0.0, 0.0),
+            scale: 1.0,
+            has_view: false,
+        }
+    }
+    // Moves the view towards the target of the current mode and returns its
+    // centre and scale. followed is the position of the followed body, or None
+    // if it no longer exists, in which case the camera goes back to auto.
+    pub fn update(
+        &mut self,
+        auto_centre: (f64, f64),
+        auto_scale: f64,
+        followed: Option<(f64, f64)>,
+    ) -> ((f64, f64), f64) {
+        if let CameraMode::Follow(_) = self.mode {
+            match followed {
+                Some(position) => self.target_centre = position,
+                None => self.mode = CameraMode::Auto,
+            }
+        }
+        if let CameraMode::Auto = self.mode {
+            self.target_centre = auto_centre;
+            // A lone body has no spread to scale to
+            if auto_scale.is_finite() && auto_scale > 0.0 {
+                self.target_scale = auto_scale;
+            }
+        }
+        if !self.has_view {
+            self.centre = self.target_centre;
+            self.scale = self.target_scale;
+            self.has_view = true;
+        }
+        let t: f64 = self.smoothing.clamp(0.0, 1.0);
+        self.centre.0 += (self.target_centre.0 - self.centre.0) * t;
+        self.centre.1 += (self.target_centre.1 - self.centre.1) * t;
+        // Ease the scale geometrically so zooming in and out look the same
+        self.scale *= (self.target_scale / self.scale).powf(t);
+        (self.centre, self.scale)
+    }
+    // Moves the view by the given canvas distance, switching to manual control
+    pub fn pan(&mut self, canvas_dx: f64, canvas_dy: f64) {
+        match self.mode {
+            CameraMode::Fixed => return,
+            CameraMode::Auto | CameraMode::Follow(_) => self.set_mode(CameraMode::Manual),
+            CameraMode::Manual => (),
+        }
+        // Panning follows the pointer directly, without easing
+        self.centre.0 -= canvas_dx / self.scale;
+        self.centre.1 -= canvas_dy / self.scale;
+        self.target_centre = self.centre;
+    }
+    // Zooms by factor keeping the simulation position under the given canvas
+    // offset from the centre in place. Following a body keeps it centred.
+    pub fn zoom(&mut self, factor: f64, canvas_offset: (f64, f64)) {
+        if factor <= 0.0 || !factor.is_finite() {
+            return;
+        }
+        match self.mode {
+            CameraMode::Fixed => return,
+            CameraMode::Auto => self.set_mode(CameraMode::Manual),
+            CameraMode::Manual | CameraMode::Follow(_) => (),
+        }
+        let new_scale: f64 = self.scale * factor;
+        if let CameraMode::Manual = self.mode {
+            self.centre.0 += canvas_offset.0 / self.scale - canvas_offset.0 / new_scale;
+            self.centre.1 += canvas_offset.1 / self.scale - canvas_offset.1 / new_scale;
+            self.target_centre = self.centre;
+        }
+        self.scale = new_scale;
+        self.target_scale = new_scale;
+    }
+    // Follows a body from the current view
+    pub fn follow(&mut self, id: u64) {
+        self.mode = CameraMode::Follow(id);
+        self.target_scale = self.scale;
+    }
+    // Shows the given width of the simulation around the given centre
+    pub fn fix(&mut self, centre: (f64, f64), scale: f64) {
+        self.mode = CameraMode::Fixed;
+        self.target_centre = centre;
+        self.target_scale = scale;
+    }
+    // The view drawn in the last frame
+    pub fn view(&self) -> ((f64, f64), f64) {
+        (self.centre, self.scale)
+    }
+    // Jumps straight to a saved mode and view
+    pub fn restore(&mut self, mode: CameraMode, centre: (f64, f64), scale: f64) {
+        self.mode = mode;
+        self.centre = centre;
+        self.target_centre = centre;
+        // Without a usable view, start from the first frame's target as at startup
+        self.has_view = scale.is_finite() && scale > 0.0;
+        if self.has_view {
+            self.scale = scale;
+            self.target_scale = scale;
+        }
+    }
+    // Switches modes, keeping the current view for manual and fixed
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        match mode {
+            CameraMode::Follow(id) => self.follow(id),
+            CameraMode::Manual | CameraMode::Fixed => {
+                self.mode = mode;
+                self.target_centre = self.centre;
+                self.target_scale = self.scale;
+            }
+            CameraMode::Auto => self.mode = mode,
+        }
+    }
+}
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
+}
@@ -0,0 +1,249 @@
+use density::interpolate_colour;
+
+use crate::constants::{STAR_COLOURS, STAR_COLOURS_LEN};
+
+use super::Simulation;
+
+// Blue for low values through white to red for high ones, so bound bodies
+// are blue and unbound ones red when colouring by energy
+const DEFAULT_GRADIENT: [[u8; 3]; 3] = [[80, 120, 255], [255, 255, 255], [255, 80, 60]];
+// The number of colours a gradient is drawn with
+const GRADIENT_LEVELS: usize = 64;
+// Values are scaled between these percentiles so a few outliers don't wash
+// out the rest
+const LOW_PERCENTILE: f64 = 0.01;
+const HIGH_PERCENTILE: f64 = 0.99;
+// Consecutive spawn batches are this far apart along the gradient, which
+// keeps any few recent batches distinct
+const BATCH_STRIDE: f64 = 0.618_033_988_749_895;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColourMode {
+    // The colour of a star, chosen by id, which carries no information
+    Star,
+    // Speed relative to the centre of mass, along the gradient
+    Speed,
+    // Specific energy relative to the centre of mass, with bound bodies in the
+    // lower half of the gradient and unbound bodies in the upper half
+    Energy,
+    // Acceleration magnitude on a log scale, along the gradient
+    Acceleration,
+    // Bodies spawned together share a colour from the gradient
+    Batch,
+}
+impl ColourMode {
+    pub fn from_name(name: &str) -> Option<ColourMode> {
+        match name {
+            "star" => Some(ColourMode::Star),
+            "speed" => Some(ColourMode::Speed),
+            "energy" => Some(ColourMode::Energy),
+            "acceleration" => Some(ColourMode::Acceleration),
+            "batch" => Some(ColourMode::Batch),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColourMode::Star => "star",
+            ColourMode::Speed => "speed",
+            ColourMode::Energy => "energy",
+            ColourMode::Acceleration => "acceleration",
+            ColourMode::Batch => "batch",
+        }
+    }
+}
+
+// Chooses each body's colour as an index into a palette, which the JS draw
+// buffer refers to by index too
+pub struct Colouring {
+    mode: ColourMode,
+    gradient: Vec<[u8; 3]>,
+    palette: Vec<String>,
+    // The palette index of each body, from the last colour_bodies()
+    indexes: Vec<usize>,
+    values: Vec<f64>,
+    sorted_values: Vec<f64>,
+}
+impl Colouring {
+    pub fn new() -> Colouring {
+        let mut colouring: Colouring = Colouring {
+            mode: ColourMode::Star,
+            gradient: DEFAULT_GRADIENT.to_vec(),
+            palette: Vec::new(),
+            indexes: Vec::new(),
+            values: Vec::new(),
+            sorted_values: Vec::new(),
+        };
+        colouring.build_palette();
+        colouring
+    }
+    pub fn mode(&self) -> ColourMode {
+        self.mode
+    }
+    pub fn set_mode(&mut self, mode: ColourMode) {
+        self.mode = mode;
+        self.build_palette();
+    }
+    // Sets the gradient from evenly spaced colours given as RGB bytes
+    pub fn set_gradient(&mut self, rgb: &[u8]) -> Result<(), String> {
+        if rgb.len() < 6 || !rgb.len().is_multiple_of(3) {
+            return Err(String::from(
+                "A gradient needs at least two colours of three bytes each",
+            ));
+        }
+        self.gradient = rgb
+            .chunks(3)
+            .map(|colour| [colour[0], colour[1], colour[2]])
+            .collect();
+        self.build_palette();
+        Ok(())
+    }
+    // The colours that the palette indexes refer to, as CSS colours
+    pub fn palette(&self) -> &[String] {
+        &self.palette
+    }
+    // The palette index of the body at the given index
+    pub fn index(&self, body: usize) -> usize {
+        self.indexes[body]
+    }
+
+    fn build_palette(&mut self) {
+        self.palette = match self.mode {
+            ColourMode::Star => STAR_COLOURS
+                .iter()
+                .map(|colour| colour.to_string())
+                .collect(),
+            _ => (0..GRADIENT_LEVELS)
+                .map(|level| {
+                    let t: f64 = level as f64 / (GRADIENT_LEVELS - 1) as f64;
+                    let [r, g, b]: [u8; 3] = interpolate_colour(&self.gradient, t);
+                    format!("rgb({},{},{})", r, g, b)
+                })
+                .collect(),
+        };
+    }
+    // The palette index for t in [0, 1]
+    fn level(t: f64) -> usize {
+        let t: f64 = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        (t * (GRADIENT_LEVELS - 1) as f64).round() as usize
+    }
+    // The values at the low and high percentiles
+    fn value_range(&mut self) -> (f64, f64) {
+        self.sorted_values.clear();
+        self.sorted_values
+            .extend(self.values.iter().filter(|value| value.is_finite()));
+        if self.sorted_values.is_empty() {
+            return (0.0, 0.0);
+        }
+        self.sorted_values.sort_unstable_by(|a, b| a.total_cmp(b));
+        let last: f64 = (self.sorted_values.len() - 1) as f64;
+        (
+            self.sorted_values[(LOW_PERCENTILE * last) as usize],
+            self.sorted_values[(HIGH_PERCENTILE * last) as usize],
+        )
+    }
+    // Maps the values linearly from their percentile range onto the palette
+    fn index_values(&mut self) {
+        let (low, high): (f64, f64) = self.value_range();
+        let range: f64 = high - low;
+        self.indexes.clear();
+        for value in self.values.iter() {
+            let t: f64 = if range > 0.0 {
+                (value - low) / range
+            } else {
+                0.0
+            };
+            self.indexes.push(Colouring::level(t));
+        }
+    }
+}
+impl Default for Colouring {
+    fn default() -> Self {
+        Colouring::new()
+    }
+}
+
+impl Simulation {
+    // Works out the palette index of every body for the colour mode
+    pub fn colour_bodies(&mut self) {
+        let num_bodies: usize = self.num_bodies;
+        let colouring: &mut Colouring = &mut self.colouring;
+        if colouring.mode == ColourMode::Star {
+            colouring.indexes.clear();
+            colouring.indexes.extend(
+                self.ids
+                    .iter()
+                    .map(|id| (id % STAR_COLOURS_LEN as u64) as usize),
+            );
+            return;
+        }
+        if colouring.mode == ColourMode::Batch {
+            colouring.indexes.clear();
+            colouring.indexes.extend(
+                self.batches
+                    .iter()
+                    .map(|&batch| Colouring::level((batch as f64 * BATCH_STRIDE).fract())),
+            );
+            return;
+        }
+        // Centre of mass position and velocity
+        let mut com: (f64, f64, f64, f64) = (0.0, 0.0, 0.0, 0.0);
+        let mut total_mass: f64 = 0.0;
+        for i in 0..num_bodies {
+            com.0 += self.x[i] * self.masses[i];
+            com.1 += self.y[i] * self.masses[i];
+            com.2 += self.vx[i] * self.masses[i];
+            com.3 += self.vy[i] * self.masses[i];
+            total_mass += self.masses[i];
+        }
+        if total_mass > 0.0 {
+            com = (
+                com.0 / total_mass,
+                com.1 / total_mass,
+                com.2 / total_mass,
+                com.3 / total_mass,
+            );
+        }
+        colouring.values.clear();
+        for i in 0..num_bodies {
+            let dvx: f64 = self.vx[i] - com.2;
+            let dvy: f64 = self.vy[i] - com.3;
+            let value: f64 = match colouring.mode {
+                ColourMode::Speed => (dvx * dvx + dvy * dvy).sqrt(),
+                // As for removing unbound bodies, the others are treated as a
+                // point mass at the centre of mass
+                ColourMode::Energy => {
+                    let dx: f64 = self.x[i] - com.0;
+                    let dy: f64 = self.y[i] - com.1;
+                    let distance: f64 = (dx * dx + dy * dy).sqrt();
+                    0.5 * (dvx * dvx + dvy * dvy)
+                        - self.gravity * (total_mass - self.masses[i]) / distance
+                }
+                ColourMode::Acceleration => {
+                    let acceleration: f64 = self.gravity.abs()
+                        * (self.ax[i] * self.ax[i] + self.ay[i] * self.ay[i]).sqrt();
+                    acceleration.ln()
+                }
+                ColourMode::Star | ColourMode::Batch => 0.0,
+            };
+            colouring.values.push(value);
+        }
+        if colouring.mode == ColourMode::Energy {
+            // Zero energy is the middle of the gradient, with the scale set by
+            // the most energetic bodies either side
+            let (low, high): (f64, f64) = colouring.value_range();
+            let scale: f64 = low.abs().max(high.abs());
+            colouring.indexes.clear();
+            for value in colouring.values.iter() {
+                let t: f64 = if scale > 0.0 {
+                    0.5 + 0.5 * value / scale
+                } else {
+                    0.5
+                };
+                colouring.indexes.push(Colouring::level(t));
+            }
+            return;
+        }
+        colouring.index_values();
+    }
+}
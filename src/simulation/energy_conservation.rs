@@ -6,14 +6,8 @@ use crate::log;
 // The kinetic energy is defined as 1/2 * v^2
 // The potential energy is defined as -G / r
 // The total energy is defined as the sum of the kinetic energy and the potential energy
-pub fn log_energy(
-    x: &Vec<f64>,
-    y: &Vec<f64>,
-    vx: &Vec<f64>,
-    vy: &Vec<f64>,
-    num_bodies: usize,
-    gravity: f64,
-) {
+#[allow(dead_code)]
+pub fn log_energy(x: &[f64], y: &[f64], vx: &[f64], vy: &[f64], num_bodies: usize, gravity: f64) {
     // Calculate the total energy
     let mut total_kinetic_energy: f64 = 0.0;
     let mut total_potential_energy: f64 = 0.0;
@@ -0,0 +1,101 @@
+use crate::constants::{ROOT_NODE_INDEX, SPAWN_PREVIEW_COLOR, SPAWN_PREVIEW_STEPS};
+use crate::draw_trail;
+
+use super::bh_tree::Tree;
+use super::calc_acceleration;
+
+// The predicted path of the bodies being spawned, so the user can aim them.
+// The centre of mass of the spawned bodies moves like a single body in the
+// field of the existing bodies, which stay where they are while the mouse is
+// held because the simulation doesn't advance.
+pub struct SpawnPreview {
+    // The number of steps predicted, 0 to turn the preview off
+    pub steps: usize,
+    // Simulation positions from the spawn point onwards
+    path: Vec<(f64, f64)>,
+    canvas_points: Vec<f64>,
+}
+impl SpawnPreview {
+    pub fn new() -> SpawnPreview {
+        SpawnPreview {
+            steps: SPAWN_PREVIEW_STEPS,
+            path: Vec::new(),
+            canvas_points: Vec::new(),
+        }
+    }
+    // Integrates the path from the given position and velocity with the same
+    // kick then drift steps as the simulation, taking the acceleration from a
+    // tree already constructed for the bodies. Without a tree the path is straight.
+    pub fn predict(
+        &mut self,
+        bh_tree: Option<&Tree>,
+        start: (f64, f64),
+        velocity: (f64, f64),
+        gravity: f64,
+        dt: f64,
+    ) {
+        self.path.clear();
+        if self.steps == 0 {
+            return;
+        }
+        let acceleration = |position: (f64, f64)| match bh_tree {
+            Some(bh_tree) => {
+                let (ax, ay): (f64, f64) = calc_acceleration::add_node_acceleration(
+                    (&position.0, &position.1),
+                    bh_tree,
+                    ROOT_NODE_INDEX,
+                    bh_tree.root_half_width,
+                );
+                (ax * gravity, ay * gravity)
+            }
+            None => (0.0, 0.0),
+        };
+        let (mut x, mut y): (f64, f64) = start;
+        let (mut vx, mut vy): (f64, f64) = velocity;
+        self.path.reserve(self.steps + 1);
+        self.path.push((x, y));
+        for _ in 0..self.steps {
+            let (ax, ay): (f64, f64) = acceleration((x, y));
+            vx += ax * dt;
+            vy += ay * dt;
+            x += vx * dt;
+            y += vy * dt;
+            if !x.is_finite() || !y.is_finite() {
+                break;
+            }
+            self.path.push((x, y));
+        }
+    }
+    pub fn clear(&mut self) {
+        self.path.clear();
+    }
+    // The predicted positions from the spawn point onwards
+    pub fn path(&self) -> &[(f64, f64)] {
+        &self.path
+    }
+    // Draws the path as a line with the same view as the bodies
+    pub fn draw(
+        &mut self,
+        com: (f64, f64),
+        scale: f64,
+        canvas_half_width: f64,
+        canvas_half_height: f64,
+    ) {
+        if self.path.len() < 2 {
+            return;
+        }
+        self.canvas_points.clear();
+        for (x, y) in self.path.iter() {
+            self.canvas_points
+                .push((x - com.0) * scale + canvas_half_width);
+            self.canvas_points
+                .push((y - com.1) * scale + canvas_half_height);
+        }
+        draw_trail(&self.canvas_points, SPAWN_PREVIEW_COLOR);
+    }
+}
+impl Default for SpawnPreview {
+    fn default() -> Self {
+        SpawnPreview::new()
+    }
+}
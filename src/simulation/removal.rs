@@ -0,0 +1,107 @@
+// Which bodies are removed after each step. Removal is off by default.
+#[derive(Clone, Copy, Default)]
+pub struct RemovalPolicy {
+    // Remove bodies further than this from the centre of mass
+    pub max_distance: Option<f64>,
+    // Remove bodies that are moving away from the centre of mass with positive energy
+    pub remove_unbound: bool,
+    // Pinned bodies are kept unless this is set
+    pub remove_pinned: bool,
+}
+impl RemovalPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_distance.is_some() || self.remove_unbound
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RemovalReason {
+    Distance,
+    Unbound,
+    // Removed with the erase tool
+    Erased,
+}
+impl RemovalReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RemovalReason::Distance => "distance",
+            RemovalReason::Unbound => "unbound",
+            RemovalReason::Erased => "erased",
+        }
+    }
+}
+
+// Finds the indexes of the bodies to remove, in order, with the reason for each.
+//
+// A body's energy treats every other body as a point mass at the centre of
+// mass, which is accurate once the body is well outside the system. Only
+// receding bodies count as unbound, so a fast body passing through the
+// centre is not removed. Pinned bodies are only removed if the policy says so.
+#[allow(clippy::too_many_arguments)]
+pub fn find_removals(
+    x: &[f64],
+    y: &[f64],
+    vx: &[f64],
+    vy: &[f64],
+    masses: &[f64],
+    fixed: &[bool],
+    gravity: f64,
+    policy: &RemovalPolicy,
+) -> Vec<(usize, RemovalReason)> {
+    let mut removals: Vec<(usize, RemovalReason)> = Vec::new();
+    let num_bodies: usize = x.len();
+    if num_bodies == 0 || !policy.is_enabled() {
+        return removals;
+    }
+    // Centre of mass position and velocity
+    let mut com: (f64, f64, f64, f64) = (0.0, 0.0, 0.0, 0.0);
+    let mut total_mass: f64 = 0.0;
+    for i in 0..num_bodies {
+        com.0 += x[i] * masses[i];
+        com.1 += y[i] * masses[i];
+        com.2 += vx[i] * masses[i];
+        com.3 += vy[i] * masses[i];
+        total_mass += masses[i];
+    }
+    com.0 /= total_mass;
+    com.1 /= total_mass;
+    com.2 /= total_mass;
+    com.3 /= total_mass;
+    for i in 0..num_bodies {
+        if fixed[i] && !policy.remove_pinned {
+            continue;
+        }
+        let dx: f64 = x[i] - com.0;
+        let dy: f64 = y[i] - com.1;
+        let distance: f64 = (dx * dx + dy * dy).sqrt();
+        if policy
+            .max_distance
+            .is_some_and(|max_distance| distance > max_distance)
+        {
+            removals.push((i, RemovalReason::Distance));
+            continue;
+        }
+        if policy.remove_unbound && distance > 0.0 {
+            let dvx: f64 = vx[i] - com.2;
+            let dvy: f64 = vy[i] - com.3;
+            let receding: bool = dx * dvx + dy * dvy > 0.0;
+            let other_mass: f64 = total_mass - masses[i];
+            let energy: f64 = 0.5 * (dvx * dvx + dvy * dvy) - gravity * other_mass / distance;
+            if receding && energy > 0.0 {
+                removals.push((i, RemovalReason::Unbound));
+            }
+        }
+    }
+    removals
+}
+
+// Removes the values at the given (ordered) indexes, keeping the rest in order
+pub fn remove_indexes<T>(values: &mut Vec<T>, removals: &[(usize, RemovalReason)]) {
+    let mut removals = removals.iter().peekable();
+    let mut index: usize = 0;
+    values.retain(|_| {
+        let keep: bool = removals.next_if(|(removed, _)| *removed == index).is_none();
+        index += 1;
+        keep
+    });
+}
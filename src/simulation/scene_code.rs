@@ -0,0 +1,300 @@
+// A compact text encoding of a scene for sharing in links.
+//
+// The bodies' positions and velocities are quantized to 16 bits over their
+// range, deflated and written as unpadded base64url, which is safe in URL
+// fragments. A few thousand bodies take a few tens of kilobytes. Ids and the
+// camera are not kept, so a shared scene starts with fresh ids in auto mode.
+//
+// Before compression the code is:
+// - the magic "SCN" and a version byte
+// - dt, gravity, spawn radius, spawn speed and scale multiplier as f64
+// - the number of bodies as u32
+// - the minimum and quantization step of x, y, vx and vy as f64
+// - the quantized x values in increasing order as LEB128 deltas, with the
+//   bodies in that order for the other columns
+// - the quantized y, vx and vy columns, each as all the high bytes then all
+//   the low bytes, which compresses better than interleaved u16s
+// - from version 2, the number of bodies that are pinned or don't have a mass
+//   of 1 as u32, then for each its position in the order above as u32, its
+//   mass as f64 and 1 if it is pinned or 0 as u8
+// - from version 3, the length of the spawn shape's name as u8, the name and
+//   the spawn spin as f64
+// All numbers are little-endian.
+
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use super::state::{BodiesState, ParametersState, SceneState, STATE_VERSION};
+use super::Simulation;
+
+const MAGIC: &[u8; 3] = b"SCN";
+const VERSION: u8 = 3;
+const LEVELS: f64 = u16::MAX as f64;
+// Larger decompressed codes are rejected rather than read into memory
+const MAX_DECODED_SIZE: u64 = 64 * 1024 * 1024;
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl Simulation {
+    pub fn export_scene_code(&self) -> Result<String, String> {
+        let mut data: Vec<u8> = Vec::with_capacity(64 + self.num_bodies * 8);
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        for value in [
+            self.dt,
+            self.gravity,
+            self.spawner.spawn_radius,
+            self.spawner.spawn_speed,
+            self.scale_multiplier,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&(self.num_bodies as u32).to_le_bytes());
+        let columns: [Vec<u16>; 4] = [&self.x, &self.y, &self.vx, &self.vy].map(|values| {
+            let (min, step): (f64, f64) = quantization(values);
+            data.extend_from_slice(&min.to_le_bytes());
+            data.extend_from_slice(&step.to_le_bytes());
+            values
+                .iter()
+                .map(|value| quantize(*value, min, step))
+                .collect()
+        });
+        // Sort the bodies by x so the x column is small deltas
+        let mut order: Vec<usize> = (0..self.num_bodies).collect();
+        order.sort_by_key(|&i| columns[0][i]);
+        let mut previous: u16 = 0;
+        for &i in &order {
+            write_varint(&mut data, (columns[0][i] - previous) as u32);
+            previous = columns[0][i];
+        }
+        for column in &columns[1..] {
+            data.extend(order.iter().map(|&i| (column[i] >> 8) as u8));
+            data.extend(order.iter().map(|&i| column[i] as u8));
+        }
+        let special: Vec<(usize, usize)> = order
+            .iter()
+            .enumerate()
+            .filter(|(_, &i)| self.masses[i] != 1.0 || self.fixed[i])
+            .map(|(position, &i)| (position, i))
+            .collect();
+        data.extend_from_slice(&(special.len() as u32).to_le_bytes());
+        for (position, i) in special {
+            data.extend_from_slice(&(position as u32).to_le_bytes());
+            data.extend_from_slice(&self.masses[i].to_le_bytes());
+            data.push(self.fixed[i] as u8);
+        }
+        let shape: &str = self.spawner.shape.name();
+        data.push(shape.len() as u8);
+        data.extend_from_slice(shape.as_bytes());
+        data.extend_from_slice(&self.spawner.spin.to_le_bytes());
+        let mut encoder: DeflateEncoder<Vec<u8>> =
+            DeflateEncoder::new(Vec::new(), Compression::best());
+        let compressed: Vec<u8> = match encoder.write_all(&data).and_then(|_| encoder.finish()) {
+            Ok(compressed) => compressed,
+            Err(err) => return Err(format!("Error compressing scene: {}", err)),
+        };
+        Ok(encode_base64url(&compressed))
+    }
+
+    // Replaces the scene with one from export_scene_code, or changes nothing
+    // if the code can't be read
+    pub fn import_scene_code(&mut self, code: &str) -> Result<(), String> {
+        let compressed: Vec<u8> = decode_base64url(code.trim())?;
+        let mut data: Vec<u8> = Vec::new();
+        if let Err(err) = DeflateDecoder::new(&compressed[..])
+            .take(MAX_DECODED_SIZE)
+            .read_to_end(&mut data)
+        {
+            return Err(format!("Error decompressing scene: {}", err));
+        }
+        let mut reader: Reader = Reader {
+            data: &data,
+            position: 0,
+        };
+        if reader.bytes(3)? != MAGIC {
+            return Err(String::from("This is not a scene code"));
+        }
+        let version: u8 = reader.bytes(1)?[0];
+        if version > VERSION {
+            return Err(format!(
+                "The scene was shared from a newer version ({}) than this one ({})",
+                version, VERSION
+            ));
+        }
+        let mut parameters: ParametersState = ParametersState {
+            dt: reader.f64()?,
+            gravity: reader.f64()?,
+            spawn_radius: reader.f64()?,
+            spawn_speed: reader.f64()?,
+            scale_multiplier: reader.f64()?,
+            spawn_shape: None,
+            spawn_spin: None,
+        };
+        let num_bodies: usize = reader.u32()? as usize;
+        let mut ranges: [(f64, f64); 4] = [(0.0, 0.0); 4];
+        for range in ranges.iter_mut() {
+            *range = (reader.f64()?, reader.f64()?);
+        }
+        // Every body takes at least 7 bytes, which bounds the allocations below
+        if num_bodies > data.len() / 7 {
+            return Err(String::from("The scene code is cut short"));
+        }
+        let mut x: Vec<f64> = Vec::with_capacity(num_bodies);
+        let mut quantized: u32 = 0;
+        for _ in 0..num_bodies {
+            quantized += reader.varint()?;
+            if quantized > u16::MAX as u32 {
+                return Err(String::from("The scene code is corrupt"));
+            }
+            x.push(ranges[0].0 + quantized as f64 * ranges[0].1);
+        }
+        let mut columns: [Vec<f64>; 3] = [Vec::new(), Vec::new(), Vec::new()];
+        for (column, &(min, step)) in columns.iter_mut().zip(&ranges[1..]) {
+            let high: &[u8] = reader.bytes(num_bodies)?;
+            let low: &[u8] = reader.bytes(num_bodies)?;
+            *column = high
+                .iter()
+                .zip(low)
+                .map(|(&high, &low)| min + u16::from_le_bytes([low, high]) as f64 * step)
+                .collect();
+        }
+        let [y, vx, vy] = columns;
+        let mut masses: Vec<f64> = vec![1.0; num_bodies];
+        let mut fixed: Vec<bool> = vec![false; num_bodies];
+        if version >= 2 {
+            let num_special: u32 = reader.u32()?;
+            for _ in 0..num_special {
+                let position: usize = reader.u32()? as usize;
+                if position >= num_bodies {
+                    return Err(String::from("The scene code is corrupt"));
+                }
+                masses[position] = reader.f64()?;
+                fixed[position] = reader.bytes(1)?[0] != 0;
+            }
+        }
+        if version >= 3 {
+            let length: usize = reader.bytes(1)?[0] as usize;
+            match String::from_utf8(reader.bytes(length)?.to_vec()) {
+                Ok(shape) => parameters.spawn_shape = Some(shape),
+                Err(_) => return Err(String::from("The scene code is corrupt")),
+            }
+            parameters.spawn_spin = Some(reader.f64()?);
+        }
+        self.load_state(SceneState {
+            version: STATE_VERSION,
+            bodies: BodiesState {
+                x,
+                y,
+                vx,
+                vy,
+                masses: Some(masses),
+                fixed: Some(fixed),
+                batches: None,
+                ids: None,
+                next_id: None,
+            },
+            parameters,
+            camera: None,
+        })
+    }
+}
+
+// Returns the minimum and step that quantize the values onto 16 bits
+fn quantization(values: &[f64]) -> (f64, f64) {
+    let min: f64 = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max: f64 = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if values.is_empty() || max <= min {
+        return (if values.is_empty() { 0.0 } else { min }, 0.0);
+    }
+    (min, (max - min) / LEVELS)
+}
+
+fn quantize(value: f64, min: f64, step: f64) -> u16 {
+    if step > 0.0 {
+        ((value - min) / step).round().clamp(0.0, LEVELS) as u16
+    } else {
+        0
+    }
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.position < length {
+            return Err(String::from("The scene code is cut short"));
+        }
+        let bytes: &'a [u8] = &self.data[self.position..self.position + length];
+        self.position += length;
+        Ok(bytes)
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+    fn varint(&mut self) -> Result<u32, String> {
+        let mut value: u32 = 0;
+        for shift in [0, 7, 14] {
+            let byte: u8 = self.bytes(1)?[0];
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(String::from("The scene code is corrupt"))
+    }
+}
+
+fn encode_base64url(data: &[u8]) -> String {
+    let mut text: String = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits: u32 = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        // Three bytes make four characters, and a partial chunk one more than its length
+        for i in 0..=chunk.len() {
+            text.push(BASE64URL[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    text
+}
+
+fn decode_base64url(text: &str) -> Result<Vec<u8>, String> {
+    let mut data: Vec<u8> = Vec::with_capacity(text.len() / 4 * 3 + 2);
+    let mut bits: u32 = 0;
+    let mut num_bits: u32 = 0;
+    for character in text.bytes() {
+        let value: u8 = match BASE64URL.iter().position(|&c| c == character) {
+            Some(value) => value as u8,
+            None => {
+                return Err(format!(
+                    "'{}' is not allowed in a scene code",
+                    character as char
+                ))
+            }
+        };
+        bits = bits << 6 | value as u32;
+        num_bits += 6;
+        if num_bits >= 8 {
+            num_bits -= 8;
+            data.push((bits >> num_bits) as u8);
+            bits &= (1 << num_bits) - 1;
+        }
+    }
+    Ok(data)
+}
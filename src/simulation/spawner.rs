@@ -1,16 +1,82 @@
-use std::thread::Thread;
-
 use rand::{rngs::ThreadRng, Rng};
 
+use std::f64::consts::TAU;
+
 use crate::{
     constants::{
-        BODIES_PER_SPAWN, SPAWN_BODY_COLOR, SPAWN_BODY_DRAW_SIZE, SPAWN_BODY_DRAW_SIZE_MOBILE,
+        BODIES_PER_SPAWN, SOFTENING, SPAWN_BODY_COLOR, SPAWN_BODY_DRAW_SIZE,
+        SPAWN_BODY_DRAW_SIZE_MOBILE, SPAWN_PLUMMER_CUTOFF, SPAWN_RING_WIDTH,
     },
     draw_arrow, draw_body, increase_num_bodies,
 };
 
 use super::bh_tree::Tree;
 
+// How new bodies are scattered around the click position, sized by the spawn radius
+#[derive(Clone, Copy, PartialEq)]
+pub enum SpawnShape {
+    // A square with sides of the spawn radius
+    Square,
+    // A disk with a diameter of the spawn radius
+    Disk,
+    // A thin ring with a diameter of the spawn radius
+    Ring,
+    // A horizontal line the length of the spawn radius
+    Line,
+    // A Plummer profile with a quarter of the spawn radius as its scale radius,
+    // dense in the middle and sparse outside
+    Plummer,
+}
+impl SpawnShape {
+    pub fn from_name(name: &str) -> Option<SpawnShape> {
+        match name {
+            "square" => Some(SpawnShape::Square),
+            "disk" => Some(SpawnShape::Disk),
+            "ring" => Some(SpawnShape::Ring),
+            "line" => Some(SpawnShape::Line),
+            "plummer" => Some(SpawnShape::Plummer),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnShape::Square => "square",
+            SpawnShape::Disk => "disk",
+            SpawnShape::Ring => "ring",
+            SpawnShape::Line => "line",
+            SpawnShape::Plummer => "plummer",
+        }
+    }
+    // A random offset from the centre of the shape
+    pub fn sample(&self, size: f64, rng: &mut ThreadRng) -> (f64, f64) {
+        let radius: f64 = size / 2.0;
+        let radial = |r: f64, rng: &mut ThreadRng| {
+            let angle: f64 = rng.gen::<f64>() * TAU;
+            (r * angle.cos(), r * angle.sin())
+        };
+        match self {
+            SpawnShape::Square => (
+                size * (rng.gen::<f64>() - 0.5),
+                size * (rng.gen::<f64>() - 0.5),
+            ),
+            // The square root spreads the bodies evenly over the area
+            SpawnShape::Disk => radial(radius * rng.gen::<f64>().sqrt(), rng),
+            SpawnShape::Ring => {
+                let r: f64 = radius * (1.0 - SPAWN_RING_WIDTH * rng.gen::<f64>());
+                radial(r, rng)
+            }
+            SpawnShape::Line => (size * (rng.gen::<f64>() - 0.5), 0.0),
+            // Inverts the fraction of the projected Plummer mass within r,
+            // r^2 / (r^2 + a^2)
+            SpawnShape::Plummer => {
+                let fraction: f64 = SPAWN_PLUMMER_CUTOFF * rng.gen::<f64>();
+                let scale_radius: f64 = size / 4.0;
+                radial(scale_radius * (fraction / (1.0 - fraction)).sqrt(), rng)
+            }
+        }
+    }
+}
+
 pub struct Spawner {
     pub spawning_mouse_x: f64,
     pub spawning_mouse_y: f64,
@@ -25,6 +91,11 @@ pub struct Spawner {
     canvas_half_height: f64,
     pub spawn_radius: f64,
     pub spawn_speed: f64,
+    pub shape: SpawnShape,
+    // Rotation of the spawned bodies about their centre as a fraction of the
+    // speed of a circular orbit around the bodies inside them, so 1 makes a
+    // rotating disk. Positive spins anticlockwise on the canvas.
+    pub spin: f64,
 }
 impl Spawner {
     pub fn new_empty() -> Spawner {
@@ -42,6 +113,8 @@ impl Spawner {
             canvas_half_height: 0.0,
             spawn_radius: 0.0,
             spawn_speed: 0.0,
+            shape: SpawnShape::Square,
+            spin: 0.0,
         }
     }
     pub fn create(&mut self, is_mobile: bool, canvas_width: f64, canvas_height: f64) {
@@ -56,18 +129,17 @@ impl Spawner {
         // convert click position from canvas to AU
         let x: f64 = (self.spawning_mouse_x - self.canvas_half_width) / scale + com.0;
         let y: f64 = (self.spawning_mouse_y - self.canvas_half_height) / scale + com.1;
+        let mut rng: ThreadRng = rand::thread_rng();
         for _ in 0..BODIES_PER_SPAWN {
             // add a random offset to the click position
-            let offset: f64 = self.spawn_radius;
-            let mut rng: ThreadRng = rand::thread_rng();
-            let click_x_au: f64 = x + offset * (rng.gen::<f64>() - 0.5);
-            let click_y_au: f64 = y + offset * (rng.gen::<f64>() - 0.5);
+            let offset: (f64, f64) = self.shape.sample(self.spawn_radius, &mut rng);
             // add one body at the click position
-            self.spawned_x.push(click_x_au);
-            self.spawned_y.push(click_y_au);
+            self.spawned_x.push(x + offset.0);
+            self.spawned_y.push(y + offset.1);
         }
         increase_num_bodies(BODIES_PER_SPAWN);
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn add_spawned_bodies_to_simulation(
         &mut self,
         mouse_x: f64,
@@ -78,32 +150,64 @@ impl Spawner {
         sim_vy: &mut Vec<f64>,
         sim_ax: &mut Vec<f64>,
         sim_ay: &mut Vec<f64>,
+        sim_masses: &mut Vec<f64>,
+        sim_fixed: &mut Vec<bool>,
+        sim_batches: &mut Vec<u64>,
+        batch: u64,
         sim_com_distances: &mut Vec<f64>,
+        sim_ids: &mut Vec<u64>,
+        sim_next_id: &mut u64,
         sim_num_bodies: &mut usize,
         sim_bh_tree: &mut Tree,
+        gravity: f64,
     ) {
         // calculate the velocity of the spawned bodies
-        let dx: f64 = mouse_x - self.spawning_mouse_x;
-        let dy: f64 = mouse_y - self.spawning_mouse_y;
-        let vx: f64 = dx * self.spawn_speed;
-        let vy: f64 = dy * self.spawn_speed;
+        let (vx, vy): (f64, f64) = self.drag_velocity(mouse_x, mouse_y);
+        let spin_velocities: Vec<(f64, f64)> =
+            spin_velocities(&self.spawned_x, &self.spawned_y, self.spin, gravity);
 
         // add all spawned bodies to the system
-        for i in 0..self.spawned_x.len() {
+        for (i, (spin_vx, spin_vy)) in spin_velocities.into_iter().enumerate() {
             sim_x.push(self.spawned_x[i]);
             sim_y.push(self.spawned_y[i]);
-            sim_vx.push(vx);
-            sim_vy.push(vy);
+            sim_vx.push(vx + spin_vx);
+            sim_vy.push(vy + spin_vy);
             sim_ax.push(0.0);
             sim_ay.push(0.0);
+            sim_masses.push(1.0);
+            sim_fixed.push(false);
+            sim_batches.push(batch);
             sim_com_distances.push(0.0);
+            sim_ids.push(*sim_next_id);
+            *sim_next_id += 1;
         }
         self.spawned_x.clear();
         self.spawned_y.clear();
         *sim_num_bodies = sim_x.len();
         *sim_bh_tree = Tree::new();
     }
-
+    // Drops the bodies spawned since the click without adding them
+    pub fn clear_spawned(&mut self) {
+        self.spawned_x.clear();
+        self.spawned_y.clear();
+    }
+    // The velocity given to the spawned bodies by dragging to the given mouse position
+    pub fn drag_velocity(&self, mouse_x: f64, mouse_y: f64) -> (f64, f64) {
+        let dx: f64 = mouse_x - self.spawning_mouse_x;
+        let dy: f64 = mouse_y - self.spawning_mouse_y;
+        (dx * self.spawn_speed, dy * self.spawn_speed)
+    }
+    // The centre of mass of the bodies spawned so far, if any
+    pub fn spawned_centre(&self) -> Option<(f64, f64)> {
+        let num_spawned: usize = self.spawned_x.len();
+        if num_spawned == 0 {
+            return None;
+        }
+        Some((
+            self.spawned_x.iter().sum::<f64>() / num_spawned as f64,
+            self.spawned_y.iter().sum::<f64>() / num_spawned as f64,
+        ))
+    }
     pub fn draw_spawned_bodies(&self, com: (f64, f64), scale: f64) {
         // draw spawned bodies (if any)
         for i in 0..self.spawned_x.len() {
@@ -134,3 +238,47 @@ impl Spawner {
         );
     }
 }
+
+// The velocity from the spin of each body at x and y, relative to the drag
+// velocity. Each body moves at spin times the speed of a circular orbit
+// around the bodies closer to their centre of mass, with the same softening
+// as the acceleration calculation. The velocities have no net momentum, so
+// the bodies move together at the drag velocity.
+pub fn spin_velocities(x: &[f64], y: &[f64], spin: f64, gravity: f64) -> Vec<(f64, f64)> {
+    let num_spawned: usize = x.len();
+    let mut velocities: Vec<(f64, f64)> = vec![(0.0, 0.0); num_spawned];
+    if spin == 0.0 || num_spawned < 2 {
+        return velocities;
+    }
+    let centre_x: f64 = x.iter().sum::<f64>() / num_spawned as f64;
+    let centre_y: f64 = y.iter().sum::<f64>() / num_spawned as f64;
+    let offsets: Vec<(f64, f64)> = (0..num_spawned)
+        .map(|i| (x[i] - centre_x, y[i] - centre_y))
+        .collect();
+    let radii: Vec<f64> = offsets
+        .iter()
+        .map(|(dx, dy)| (dx * dx + dy * dy).sqrt())
+        .collect();
+    let mut order: Vec<usize> = (0..num_spawned).collect();
+    order.sort_by(|&a, &b| radii[a].total_cmp(&radii[b]));
+    // The mass inside each body is the number of bodies before it (mass is 1)
+    for (enclosed, &i) in order.iter().enumerate() {
+        let r: f64 = radii[i];
+        if r == 0.0 {
+            continue;
+        }
+        // Nothing can orbit without attractive gravity
+        let speed_squared: f64 = gravity * enclosed as f64 * r * r / (r * r * r + SOFTENING);
+        let speed: f64 = spin * speed_squared.max(0.0).sqrt();
+        // (dy, -dx) is anticlockwise on the canvas, where y points down
+        velocities[i] = (speed * offsets[i].1 / r, -speed * offsets[i].0 / r);
+    }
+    // Take out any net motion
+    let mean_vx: f64 = velocities.iter().map(|v| v.0).sum::<f64>() / num_spawned as f64;
+    let mean_vy: f64 = velocities.iter().map(|v| v.1).sum::<f64>() / num_spawned as f64;
+    for velocity in velocities.iter_mut() {
+        velocity.0 -= mean_vx;
+        velocity.1 -= mean_vy;
+    }
+    velocities
+}
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::camera::CameraMode;
+use super::spawner::SpawnShape;
+use super::Simulation;
+
+// Bump when the format changes, and keep reading older versions with serde defaults
+pub const STATE_VERSION: u32 = 1;
+
+// A saved scene: the bodies, the parameters the user can change and the camera
+#[derive(Serialize, Deserialize)]
+pub struct SceneState {
+    pub version: u32,
+    pub bodies: BodiesState,
+    pub parameters: ParametersState,
+    #[serde(default)]
+    pub camera: Option<CameraState>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BodiesState {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub vx: Vec<f64>,
+    pub vy: Vec<f64>,
+    // A mass of 1 for every body if missing
+    #[serde(default)]
+    pub masses: Option<Vec<f64>>,
+    // Whether each body is pinned in place, none if missing
+    #[serde(default)]
+    pub fixed: Option<Vec<bool>>,
+    // The spawn batch each body was added in, all the same if missing
+    #[serde(default)]
+    pub batches: Option<Vec<u64>>,
+    #[serde(default)]
+    pub ids: Option<Vec<u64>>,
+    #[serde(default)]
+    pub next_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParametersState {
+    pub dt: f64,
+    pub gravity: f64,
+    pub spawn_radius: f64,
+    pub spawn_speed: f64,
+    pub scale_multiplier: f64,
+    // The name of the spawn shape, a square if missing
+    #[serde(default)]
+    pub spawn_shape: Option<String>,
+    // No spin if missing
+    #[serde(default)]
+    pub spawn_spin: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CameraState {
+    // "auto", "manual", "follow" or "fixed"
+    pub mode: String,
+    #[serde(default)]
+    pub followed_id: Option<u64>,
+    pub centre: (f64, f64),
+    pub scale: f64,
+}
+
+impl Simulation {
+    // Serializes the scene to JSON
+    pub fn export_state(&self) -> Result<String, String> {
+        let (centre, scale): ((f64, f64), f64) = self.camera.view();
+        let state: SceneState = SceneState {
+            version: STATE_VERSION,
+            bodies: BodiesState {
+                x: self.x.clone(),
+                y: self.y.clone(),
+                vx: self.vx.clone(),
+                vy: self.vy.clone(),
+                masses: Some(self.masses.clone()),
+                fixed: Some(self.fixed.clone()),
+                batches: Some(self.batches.clone()),
+                ids: Some(self.ids.clone()),
+                next_id: Some(self.next_id),
+            },
+            parameters: ParametersState {
+                dt: self.dt,
+                gravity: self.gravity,
+                spawn_radius: self.spawner.spawn_radius,
+                spawn_speed: self.spawner.spawn_speed,
+                scale_multiplier: self.scale_multiplier,
+                spawn_shape: Some(self.spawner.shape.name().to_string()),
+                spawn_spin: Some(self.spawner.spin),
+            },
+            camera: Some(CameraState {
+                mode: self.camera.mode.name().to_string(),
+                followed_id: match self.camera.mode {
+                    CameraMode::Follow(id) => Some(id),
+                    _ => None,
+                },
+                centre,
+                scale,
+            }),
+        };
+        match serde_json::to_string(&state) {
+            Ok(json) => Ok(json),
+            Err(err) => Err(format!("Error saving scene: {}", err)),
+        }
+    }
+
+    // Replaces the scene with one saved by export_state. Nothing is changed if
+    // the JSON is not a valid scene.
+    pub fn import_state(&mut self, json: &str) -> Result<(), String> {
+        let state: SceneState = match serde_json::from_str(json) {
+            Ok(state) => state,
+            Err(err) => return Err(format!("Error reading scene: {}", err)),
+        };
+        self.load_state(state)
+    }
+
+    // Replaces the scene, or changes nothing if the state is invalid
+    pub fn load_state(&mut self, state: SceneState) -> Result<(), String> {
+        if state.version > STATE_VERSION {
+            return Err(format!(
+                "The scene was saved by a newer version ({}) than this one ({})",
+                state.version, STATE_VERSION
+            ));
+        }
+        let bodies: BodiesState = state.bodies;
+        let num_bodies: usize = bodies.x.len();
+        // Every field has a value per body, taking the number of bodies from x
+        let lengths: [(&str, Option<usize>); 7] = [
+            ("y", Some(bodies.y.len())),
+            ("vx", Some(bodies.vx.len())),
+            ("vy", Some(bodies.vy.len())),
+            ("masses", bodies.masses.as_ref().map(Vec::len)),
+            ("fixed", bodies.fixed.as_ref().map(Vec::len)),
+            ("batches", bodies.batches.as_ref().map(Vec::len)),
+            ("ids", bodies.ids.as_ref().map(Vec::len)),
+        ];
+        for (name, length) in lengths {
+            if let Some(length) = length.filter(|&length| length != num_bodies) {
+                return Err(format!(
+                    "The scene has {} values for '{}' but {} bodies",
+                    length, name, num_bodies
+                ));
+            }
+        }
+        let values = [&bodies.x, &bodies.y, &bodies.vx, &bodies.vy];
+        if values
+            .iter()
+            .any(|v| v.iter().any(|value| !value.is_finite()))
+        {
+            return Err(String::from(
+                "The scene has non-finite positions or velocities",
+            ));
+        }
+        if bodies
+            .masses
+            .as_ref()
+            .is_some_and(|m| m.iter().any(|mass| !(mass.is_finite() && *mass > 0.0)))
+        {
+            return Err(String::from(
+                "Every body in the scene must have a positive mass",
+            ));
+        }
+        let parameters: ParametersState = state.parameters;
+        let spawn_shape: SpawnShape = match &parameters.spawn_shape {
+            Some(name) => match SpawnShape::from_name(name) {
+                Some(shape) => shape,
+                None => return Err(format!("Unknown spawn shape '{}'", name)),
+            },
+            None => SpawnShape::Square,
+        };
+        let spawn_spin: f64 = parameters.spawn_spin.unwrap_or(0.0);
+        let values: [(&str, f64); 6] = [
+            ("time step", parameters.dt),
+            ("gravity", parameters.gravity),
+            ("spawn radius", parameters.spawn_radius),
+            ("spawn speed", parameters.spawn_speed),
+            ("scale multiplier", parameters.scale_multiplier),
+            ("spawn spin", spawn_spin),
+        ];
+        if let Some((name, _)) = values.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(format!("The {} must be finite", name));
+        }
+        let ids: Vec<u64> = bodies
+            .ids
+            .unwrap_or_else(|| (0..num_bodies as u64).collect());
+        // Ids pick out bodies to follow and colour, so each must be unique
+        let mut unique_ids: HashSet<u64> = HashSet::with_capacity(num_bodies);
+        if let Some(id) = ids.iter().find(|&&id| !unique_ids.insert(id)) {
+            return Err(format!("More than one body in the scene has id {}", id));
+        }
+        // The next body added needs an id after every existing one
+        let mut next_id: u64 = bodies.next_id.unwrap_or(0);
+        for id in &ids {
+            match id.checked_add(1) {
+                Some(after) => next_id = next_id.max(after),
+                None => return Err(format!("Body id {} is too large", id)),
+            }
+        }
+
+        self.x = bodies.x;
+        self.y = bodies.y;
+        self.vx = bodies.vx;
+        self.vy = bodies.vy;
+        self.ax = vec![0.0; num_bodies];
+        self.ay = vec![0.0; num_bodies];
+        self.masses = bodies.masses.unwrap_or_else(|| vec![1.0; num_bodies]);
+        self.fixed = bodies.fixed.unwrap_or_else(|| vec![false; num_bodies]);
+        self.batches = bodies.batches.unwrap_or_else(|| vec![0; num_bodies]);
+        self.batch = self
+            .batches
+            .iter()
+            .max()
+            .map_or(0, |batch| batch.saturating_add(1));
+        self.com_distances = vec![0.0; num_bodies];
+        self.ids = ids;
+        self.next_id = next_id;
+        self.num_bodies = num_bodies;
+        self.clicked = false;
+        self.spawner.clear_spawned();
+        self.spawn_preview.clear();
+        self.tool_press_position = None;
+        self.selected.clear();
+        self.trails.clear();
+
+        self.dt = parameters.dt;
+        self.gravity = parameters.gravity;
+        self.spawner.spawn_radius = parameters.spawn_radius;
+        self.spawner.spawn_speed = parameters.spawn_speed;
+        self.scale_multiplier = parameters.scale_multiplier;
+        self.spawner.shape = spawn_shape;
+        self.spawner.spin = spawn_spin;
+
+        match state.camera {
+            Some(camera) => {
+                let mode: CameraMode = match (camera.mode.as_str(), camera.followed_id) {
+                    ("follow", Some(id)) => CameraMode::Follow(id),
+                    (name, _) => CameraMode::from_name(name).unwrap_or(CameraMode::Auto),
+                };
+                self.camera.restore(mode, camera.centre, camera.scale);
+            }
+            None => self.camera.set_mode(CameraMode::Auto),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use std::f64::consts::TAU;
+
+use crate::constants::{TOOL_BRUSH_COLOR, TOOL_BRUSH_SEGMENTS};
+use crate::{decrease_num_bodies, draw_arrow, draw_trail, increase_num_bodies};
+
+use super::removal::RemovalReason;
+use super::Simulation;
+
+// What pressing the mouse on the canvas does
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tool {
+    // Adds bodies with on_click and off_click
+    Spawn,
+    // Removes the bodies under the brush while held
+    Erase,
+    // Moves the bodies under the brush when pressed with the pointer
+    Grab,
+    // Gives the bodies under the brush when pressed the velocity of the drag
+    // on release, scaled like the velocity of spawned bodies
+    Fling,
+    // Adds a single body with the place mass, flung like the fling tool
+    // unless it is pinned
+    Place,
+}
+impl Tool {
+    pub fn from_name(name: &str) -> Option<Tool> {
+        match name {
+            "spawn" => Some(Tool::Spawn),
+            "erase" => Some(Tool::Erase),
+            "grab" => Some(Tool::Grab),
+            "fling" => Some(Tool::Fling),
+            "place" => Some(Tool::Place),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Spawn => "spawn",
+            Tool::Erase => "erase",
+            Tool::Grab => "grab",
+            Tool::Fling => "fling",
+            Tool::Place => "place",
+        }
+    }
+}
+
+// The erase, grab, fling and place tools. Like spawning, the simulation doesn't
+// advance while a tool is held, so the selected indexes stay valid.
+impl Simulation {
+    // Starts using the current tool at a canvas position
+    pub fn tool_press(&mut self, canvas_x: f64, canvas_y: f64) {
+        self.tool_press_position = Some((canvas_x, canvas_y));
+        self.tool_last_position = (canvas_x, canvas_y);
+        self.selected.clear();
+        match self.tool {
+            Tool::Spawn => (),
+            Tool::Erase => self.erase(canvas_x, canvas_y),
+            Tool::Grab | Tool::Fling => self.selected = self.bodies_in_brush(canvas_x, canvas_y),
+            Tool::Place => {
+                let x: f64 = (canvas_x - self.canvas_half_width) / self.scale + self.com.0;
+                let y: f64 = (canvas_y - self.canvas_half_height) / self.scale + self.com.1;
+                self.start_batch();
+                self.add_massive_body(x, y, 0.0, 0.0, self.place_mass, self.place_fixed);
+                self.selected = vec![self.num_bodies - 1];
+                self.fit_tree_root();
+                increase_num_bodies(1);
+            }
+        }
+    }
+    // Moves the held tool to a canvas position
+    pub fn tool_drag(&mut self, canvas_x: f64, canvas_y: f64) {
+        if self.tool_press_position.is_none() {
+            return;
+        }
+        match self.tool {
+            Tool::Spawn | Tool::Fling | Tool::Place => (),
+            Tool::Erase => self.erase(canvas_x, canvas_y),
+            Tool::Grab => {
+                let dx: f64 = (canvas_x - self.tool_last_position.0) / self.scale;
+                let dy: f64 = (canvas_y - self.tool_last_position.1) / self.scale;
+                for &i in &self.selected {
+                    self.x[i] += dx;
+                    self.y[i] += dy;
+                }
+                // The tree is rebuilt before it is next used, around a root that fits the moved bodies
+                self.fit_tree_root();
+            }
+        }
+        self.tool_last_position = (canvas_x, canvas_y);
+    }
+    // Stops using the tool at a canvas position
+    pub fn tool_release(&mut self, canvas_x: f64, canvas_y: f64) {
+        self.tool_drag(canvas_x, canvas_y);
+        if let (Tool::Fling | Tool::Place, Some(press)) = (self.tool, self.tool_press_position) {
+            let vx: f64 = (canvas_x - press.0) * self.spawner.spawn_speed;
+            let vy: f64 = (canvas_y - press.1) * self.spawner.spawn_speed;
+            // Pinned bodies never move
+            for &i in self.selected.iter().filter(|&&i| !self.fixed[i]) {
+                self.vx[i] = vx;
+                self.vy[i] = vy;
+            }
+        }
+        self.tool_press_position = None;
+        self.selected.clear();
+    }
+    pub fn is_tool_held(&self) -> bool {
+        self.tool_press_position.is_some()
+    }
+
+    // Removes the bodies under the brush at a canvas position
+    fn erase(&mut self, canvas_x: f64, canvas_y: f64) {
+        let removals: Vec<(usize, RemovalReason)> = self
+            .bodies_in_brush(canvas_x, canvas_y)
+            .into_iter()
+            .map(|i| (i, RemovalReason::Erased))
+            .collect();
+        if removals.is_empty() {
+            return;
+        }
+        self.remove_at(&removals);
+        self.fit_tree_root();
+        decrease_num_bodies(removals.len());
+    }
+
+    // The indexes, in order, of the bodies drawn within the brush radius of a canvas position
+    fn bodies_in_brush(&self, canvas_x: f64, canvas_y: f64) -> Vec<usize> {
+        let x: f64 = (canvas_x - self.canvas_half_width) / self.scale + self.com.0;
+        let y: f64 = (canvas_y - self.canvas_half_height) / self.scale + self.com.1;
+        let radius: f64 = self.brush_radius / self.scale;
+        (0..self.num_bodies)
+            .filter(|&i| (self.x[i] - x).powi(2) + (self.y[i] - y).powi(2) <= radius * radius)
+            .collect()
+    }
+
+    // Draws the brush around the pointer, and the drag while flinging or placing
+    pub(super) fn draw_tool(&self) {
+        let (mouse_x, mouse_y): (f64, f64) =
+            (self.spawner.current_mouse_x, self.spawner.current_mouse_y);
+        match (self.tool, self.tool_press_position) {
+            (Tool::Spawn, _) => return,
+            (Tool::Place, Some(press)) if !self.place_fixed => {
+                draw_arrow(press.0, press.1, mouse_x, mouse_y, TOOL_BRUSH_COLOR);
+                return;
+            }
+            (Tool::Place, _) => return,
+            _ => (),
+        }
+        let (centre_x, centre_y): (f64, f64) = match (self.tool, self.tool_press_position) {
+            (Tool::Fling, Some(press)) => press,
+            _ => (mouse_x, mouse_y),
+        };
+        let brush: Vec<f64> = (0..=TOOL_BRUSH_SEGMENTS)
+            .flat_map(|segment| {
+                let angle: f64 = TAU * segment as f64 / TOOL_BRUSH_SEGMENTS as f64;
+                [
+                    centre_x + self.brush_radius * angle.cos(),
+                    centre_y + self.brush_radius * angle.sin(),
+                ]
+            })
+            .collect();
+        draw_trail(&brush, TOOL_BRUSH_COLOR);
+        if let (Tool::Fling, Some(press)) = (self.tool, self.tool_press_position) {
+            draw_arrow(press.0, press.1, mouse_x, mouse_y, TOOL_BRUSH_COLOR);
+        }
+    }
+}
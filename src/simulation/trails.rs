@@ -0,0 +1,87 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::constants::{STAR_COLOURS, STAR_COLOURS_LEN, TRAIL_LENGTH};
+use crate::draw_trail;
+
+// The recent positions of tracked bodies, recorded every step and drawn as lines
+pub struct Trails {
+    // Tracked body ids with their positions from oldest to newest
+    tracked: Vec<(u64, VecDeque<(f64, f64)>)>,
+    pub max_length: usize,
+    canvas_points: Vec<f64>,
+}
+impl Trails {
+    pub fn new() -> Trails {
+        Trails {
+            tracked: Vec::new(),
+            max_length: TRAIL_LENGTH,
+            canvas_points: Vec::new(),
+        }
+    }
+    pub fn track(&mut self, id: u64) {
+        if !self.tracked.iter().any(|(tracked_id, _)| *tracked_id == id) {
+            self.tracked.push((id, VecDeque::new()));
+        }
+    }
+    pub fn untrack(&mut self, id: u64) {
+        self.tracked.retain(|(tracked_id, _)| *tracked_id != id);
+    }
+    pub fn clear(&mut self) {
+        self.tracked.clear();
+    }
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+        for (_, trail) in self.tracked.iter_mut() {
+            while trail.len() > max_length {
+                trail.pop_front();
+            }
+        }
+    }
+    // Adds the current position of each tracked body to its trail
+    pub fn record(&mut self, ids: &[u64], x: &[f64], y: &[f64]) {
+        if self.tracked.is_empty() {
+            return;
+        }
+        let indexes: HashMap<u64, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect();
+        for (id, trail) in self.tracked.iter_mut() {
+            if let Some(&index) = indexes.get(id) {
+                trail.push_back((x[index], y[index]));
+                while trail.len() > self.max_length {
+                    trail.pop_front();
+                }
+            }
+        }
+    }
+    // Draws each trail in its body's colour with the same view as the bodies
+    pub fn draw(
+        &mut self,
+        com: (f64, f64),
+        scale: f64,
+        canvas_half_width: f64,
+        canvas_half_height: f64,
+    ) {
+        for (id, trail) in self.tracked.iter() {
+            if trail.len() < 2 {
+                continue;
+            }
+            self.canvas_points.clear();
+            for (x, y) in trail.iter() {
+                self.canvas_points
+                    .push((x - com.0) * scale + canvas_half_width);
+                self.canvas_points
+                    .push((y - com.1) * scale + canvas_half_height);
+            }
+            let color: &str = STAR_COLOURS[(id % STAR_COLOURS_LEN as u64) as usize];
+            draw_trail(&self.canvas_points, color);
+        }
+    }
+}
+impl Default for Trails {
+    fn default() -> Self {
+        Trails::new()
+    }
+}
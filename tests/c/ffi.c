@@ -0,0 +1,60 @@
+// Drives the simulation core from C through the generated header. Exits with
+// status 0 if every check passes, printing the first failed check otherwise.
+#include <math.h>
+#include <stdio.h>
+
+#include "space_clicker.h"
+
+#define CHECK(condition) \
+    if (!(condition)) { \
+        fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #condition); \
+        return 1; \
+    }
+
+int main(void) {
+    SpaceClickerSimulation *simulation = space_clicker_create();
+    CHECK(simulation != NULL);
+    // Two unit masses a unit apart on a circular orbit about their centre of mass
+    double speed = sqrt(0.5 / 1.1);
+    double x[2] = {-0.5, 0.5};
+    double y[2] = {0.0, 0.0};
+    double vx[2] = {0.0, 0.0};
+    double vy[2] = {-speed, speed};
+    CHECK(space_clicker_add_bodies(simulation, x, y, vx, vy, NULL, NULL, 2) == 2);
+    space_clicker_set_dt(simulation, 0.001);
+    space_clicker_set_gravity(simulation, 1.0);
+    space_clicker_set_removal(simulation, 0.0, false);
+    space_clicker_step(simulation, 1000);
+    CHECK(space_clicker_num_bodies(simulation) == 2);
+
+    double out_x[2], out_y[2], out_vx[2], out_vy[2];
+    CHECK(space_clicker_copy_positions(simulation, out_x, out_y, 2) == 2);
+    CHECK(space_clicker_copy_velocities(simulation, out_vx, out_vy, 2) == 2);
+    double separation = hypot(out_x[1] - out_x[0], out_y[1] - out_y[0]);
+    CHECK(fabs(separation - 1.0) < 0.05);
+    CHECK(fabs(out_vx[0] + out_vx[1]) < 1e-9);
+    CHECK(fabs(out_vy[0] + out_vy[1]) < 1e-9);
+    uint64_t ids[2];
+    CHECK(space_clicker_copy_ids(simulation, ids, 2) == 2);
+    CHECK(ids[0] == 0 && ids[1] == 1);
+    space_clicker_destroy(simulation);
+
+    // A heavy pinned body and a light one far away, which is only removed
+    // once the policy removes pinned bodies
+    simulation = space_clicker_create();
+    double pinned_x[2] = {0.0, 100.0};
+    double pinned_masses[2] = {1000.0, 1.0};
+    bool fixed[2] = {true, true};
+    CHECK(space_clicker_add_bodies(simulation, pinned_x, y, NULL, NULL, pinned_masses, fixed, 2) == 2);
+    space_clicker_set_removal(simulation, 50.0, false);
+    space_clicker_step(simulation, 1);
+    CHECK(space_clicker_num_bodies(simulation) == 2);
+    space_clicker_set_remove_pinned(simulation, true);
+    space_clicker_step(simulation, 1);
+    CHECK(space_clicker_num_bodies(simulation) == 1);
+    CHECK(space_clicker_copy_positions(simulation, out_x, out_y, 2) == 1);
+    CHECK(out_x[0] == 0.0 && out_y[0] == 0.0);
+    space_clicker_destroy(simulation);
+    space_clicker_destroy(NULL);
+    return 0;
+}
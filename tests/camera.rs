@@ -0,0 +1,109 @@
+// Moves the camera through its modes without drawing anything
+use space_clicker::simulation::camera::{Camera, CameraMode};
+
+// A camera that jumps straight to its targets
+fn camera() -> Camera {
+    let mut camera: Camera = Camera::new();
+    camera.smoothing = 1.0;
+    camera
+}
+
+#[test]
+fn auto_mode_keeps_its_scale_for_a_lone_body() {
+    let mut camera: Camera = camera();
+    assert_eq!(camera.update((1.0, 2.0), 4.0, None), ((1.0, 2.0), 4.0));
+    // One body has no spread, so its auto scale is infinite
+    assert_eq!(
+        camera.update((3.0, 4.0), f64::INFINITY, None),
+        ((3.0, 4.0), 4.0)
+    );
+    assert_eq!(camera.update((3.0, 4.0), 0.0, None), ((3.0, 4.0), 4.0));
+}
+
+#[test]
+fn view_eases_towards_the_target() {
+    let mut camera: Camera = Camera::new();
+    camera.smoothing = 0.5;
+    camera.update((0.0, 0.0), 1.0, None);
+    let ((x, y), scale) = camera.update((2.0, -4.0), 4.0, None);
+    assert_eq!((x, y), (1.0, -2.0));
+    // The scale moves half way geometrically
+    assert!((scale - 2.0).abs() < 1e-12, "{}", scale);
+}
+
+#[test]
+fn losing_the_followed_body_goes_back_to_auto() {
+    let mut camera: Camera = camera();
+    camera.update((0.0, 0.0), 2.0, None);
+    camera.set_mode(CameraMode::Follow(7));
+    assert_eq!(
+        camera.update((0.0, 0.0), 5.0, Some((3.0, 1.0))),
+        ((3.0, 1.0), 2.0)
+    );
+    // The followed body is removed, leaving one body with an infinite auto scale
+    camera.smoothing = 0.5;
+    for _ in 0..3 {
+        let ((x, y), scale) = camera.update((1.0, 1.0), f64::INFINITY, None);
+        assert!(x.is_finite() && y.is_finite());
+        assert_eq!(scale, 2.0);
+    }
+    assert!(camera.mode == CameraMode::Auto);
+}
+
+#[test]
+fn panning_switches_to_manual() {
+    let mut camera: Camera = camera();
+    camera.update((10.0, 10.0), 2.0, None);
+    camera.pan(4.0, -6.0);
+    assert!(camera.mode == CameraMode::Manual);
+    assert_eq!(camera.view(), ((8.0, 13.0), 2.0));
+    // Manual views stay put
+    assert_eq!(camera.update((0.0, 0.0), 1.0, None), ((8.0, 13.0), 2.0));
+    // Fixed views can't be panned
+    camera.set_mode(CameraMode::Fixed);
+    camera.pan(4.0, -6.0);
+    assert_eq!(camera.view(), ((8.0, 13.0), 2.0));
+}
+
+#[test]
+fn zooming_keeps_the_point_under_the_pointer() {
+    let mut camera: Camera = camera();
+    camera.update((1.0, -1.0), 2.0, None);
+    let offset: (f64, f64) = (20.0, -10.0);
+    let under_pointer = |camera: &Camera| {
+        let ((x, y), scale) = camera.view();
+        (x + offset.0 / scale, y + offset.1 / scale)
+    };
+    let before: (f64, f64) = under_pointer(&camera);
+    camera.zoom(2.5, offset);
+    assert!(camera.mode == CameraMode::Manual);
+    assert_eq!(camera.view().1, 5.0);
+    let after: (f64, f64) = under_pointer(&camera);
+    assert!((after.0 - before.0).abs() < 1e-12 && (after.1 - before.1).abs() < 1e-12);
+    // Bad factors are ignored
+    for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        camera.zoom(factor, offset);
+        assert_eq!(camera.view().1, 5.0);
+    }
+    // Following a body keeps it centred
+    camera.set_mode(CameraMode::Follow(1));
+    camera.update((0.0, 0.0), 1.0, Some((4.0, 4.0)));
+    camera.zoom(0.5, offset);
+    assert_eq!(
+        camera.update((0.0, 0.0), 1.0, Some((4.0, 4.0))),
+        ((4.0, 4.0), 2.5)
+    );
+}
+
+#[test]
+fn restore_jumps_to_a_saved_view() {
+    let mut camera: Camera = Camera::new();
+    camera.restore(CameraMode::Manual, (5.0, 6.0), 3.0);
+    assert_eq!(camera.update((0.0, 0.0), 1.0, None), ((5.0, 6.0), 3.0));
+    // Without a usable scale the first frame starts from the auto view
+    for scale in [0.0, f64::NAN] {
+        let mut camera: Camera = Camera::new();
+        camera.restore(CameraMode::Auto, (5.0, 6.0), scale);
+        assert_eq!(camera.update((1.0, 2.0), 4.0, None), ((1.0, 2.0), 4.0));
+    }
+}
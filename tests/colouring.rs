@@ -0,0 +1,71 @@
+// Colours bodies along a gradient without drawing them
+use space_clicker::simulation::colouring::ColourMode;
+use space_clicker::simulation::Simulation;
+
+// The middle of the 64 level gradient lies between levels 31 and 32
+const MIDDLE: f64 = 31.5;
+
+#[test]
+fn gradients_need_whole_colours() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.colouring.set_mode(ColourMode::Speed);
+    for rgb in [&[][..], &[1, 2, 3], &[1, 2, 3, 4], &[1, 2, 3, 4, 5, 6, 7]] {
+        assert!(simulation.colouring.set_gradient(rgb).is_err());
+    }
+    simulation
+        .colouring
+        .set_gradient(&[0, 0, 0, 255, 255, 255])
+        .unwrap();
+    let palette: &[String] = simulation.colouring.palette();
+    assert_eq!(palette.len(), 64);
+    assert_eq!(palette[0], "rgb(0,0,0)");
+    assert_eq!(palette[63], "rgb(255,255,255)");
+}
+
+#[test]
+fn speeds_are_scaled_between_percentiles() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    // Pairs of bodies moving apart at each speed, so the centre of mass is at
+    // rest, and one fast pair that would wash out the rest without percentiles
+    let speeds: Vec<f64> = (0..100).map(|speed| speed as f64).chain([1e6]).collect();
+    for (i, &speed) in speeds.iter().enumerate() {
+        simulation.add_body(i as f64, 0.0, speed, 0.0);
+        simulation.add_body(-(i as f64), 0.0, -speed, 0.0);
+    }
+    simulation.colouring.set_mode(ColourMode::Speed);
+    simulation.colour_bodies();
+    let level = |speed: usize| simulation.colouring.index(2 * speed);
+    // The 1% and 99% percentiles are speeds of 1 and 99
+    assert_eq!(level(0), 0);
+    assert_eq!(level(1), 0);
+    assert_eq!(level(99), 63);
+    assert_eq!(level(100), 63);
+    assert!((level(50) as f64 - MIDDLE).abs() <= 0.5, "{}", level(50));
+    // Faster bodies are never further down the gradient
+    assert!((1..100).all(|speed| level(speed) >= level(speed - 1)));
+}
+
+#[test]
+fn bound_bodies_are_below_the_middle_of_the_gradient() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.gravity = 1.0;
+    simulation.add_massive_body(0.0, 0.0, 0.0, 0.0, 1000.0, true);
+    // Slow bodies near a heavy one are bound, fast ones are not
+    for (x, vy) in [(1.0, 10.0), (-2.0, 5.0), (1.0, 100.0), (0.0, 150.0)] {
+        simulation.add_body(x, 1.0 - x.abs(), 0.0, vy);
+    }
+    simulation.colouring.set_mode(ColourMode::Energy);
+    simulation.colour_bodies();
+    for bound in [1, 2] {
+        assert!((simulation.colouring.index(bound) as f64) < MIDDLE);
+    }
+    for unbound in [3, 4] {
+        assert!((simulation.colouring.index(unbound) as f64) > MIDDLE);
+    }
+    // Without gravity every moving body is unbound
+    simulation.gravity = 0.0;
+    simulation.colour_bodies();
+    for body in 1..5 {
+        assert!((simulation.colouring.index(body) as f64) > MIDDLE);
+    }
+}
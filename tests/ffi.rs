@@ -0,0 +1,275 @@
+// Drives the simulation core through the C API the same way native code would
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::{env, ptr};
+
+use space_clicker::ffi::*;
+
+#[test]
+fn two_body_orbit() {
+    unsafe {
+        let simulation = space_clicker_create();
+        assert!(!simulation.is_null());
+        assert_eq!(space_clicker_num_bodies(simulation), 0);
+        // Two unit masses a unit apart on a circular orbit about their centre of mass,
+        // where the acceleration is 1 / (1 + softening) with the core's softening of 0.1
+        let speed: f64 = (0.5f64 / 1.1).sqrt();
+        let x: [f64; 2] = [-0.5, 0.5];
+        let y: [f64; 2] = [0.0, 0.0];
+        let vx: [f64; 2] = [0.0, 0.0];
+        let vy: [f64; 2] = [-speed, speed];
+        let num_bodies: usize = space_clicker_add_bodies(
+            simulation,
+            x.as_ptr(),
+            y.as_ptr(),
+            vx.as_ptr(),
+            vy.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            2,
+        );
+        assert_eq!(num_bodies, 2);
+        space_clicker_set_dt(simulation, 0.001);
+        space_clicker_set_gravity(simulation, 1.0);
+        space_clicker_step(simulation, 1000);
+
+        let mut out_x: [f64; 2] = [0.0; 2];
+        let mut out_y: [f64; 2] = [0.0; 2];
+        let copied: usize =
+            space_clicker_copy_positions(simulation, out_x.as_mut_ptr(), out_y.as_mut_ptr(), 2);
+        assert_eq!(copied, 2);
+        // The bodies have moved but stay about a unit apart around the origin
+        assert!(out_y[1] > 0.1);
+        let separation: f64 =
+            ((out_x[1] - out_x[0]).powi(2) + (out_y[1] - out_y[0]).powi(2)).sqrt();
+        assert!((separation - 1.0).abs() < 0.05, "separation {}", separation);
+        assert!((out_x[0] + out_x[1]).abs() < 1e-9);
+        assert!((out_y[0] + out_y[1]).abs() < 1e-9);
+
+        // Momentum is conserved
+        let mut out_vx: [f64; 2] = [0.0; 2];
+        let mut out_vy: [f64; 2] = [0.0; 2];
+        let copied: usize =
+            space_clicker_copy_velocities(simulation, out_vx.as_mut_ptr(), out_vy.as_mut_ptr(), 2);
+        assert_eq!(copied, 2);
+        assert!((out_vx[0] + out_vx[1]).abs() < 1e-9);
+        assert!((out_vy[0] + out_vy[1]).abs() < 1e-9);
+
+        space_clicker_destroy(simulation);
+    }
+}
+
+#[test]
+fn partial_copies_and_null_arguments() {
+    unsafe {
+        let simulation = space_clicker_create();
+        let x: [f64; 3] = [0.0, 1.0, 2.0];
+        let y: [f64; 3] = [0.0, 1.0, 0.0];
+        // Velocities default to zero
+        assert_eq!(
+            space_clicker_add_bodies(
+                simulation,
+                x.as_ptr(),
+                y.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                3
+            ),
+            3
+        );
+        let mut out_vx: [f64; 3] = [1.0; 3];
+        let mut out_vy: [f64; 3] = [1.0; 3];
+        space_clicker_copy_velocities(simulation, out_vx.as_mut_ptr(), out_vy.as_mut_ptr(), 3);
+        assert_eq!(out_vx, [0.0; 3]);
+        assert_eq!(out_vy, [0.0; 3]);
+        // Copies stop at the capacity
+        let mut out_x: [f64; 2] = [0.0; 2];
+        let mut out_y: [f64; 2] = [0.0; 2];
+        assert_eq!(
+            space_clicker_copy_positions(simulation, out_x.as_mut_ptr(), out_y.as_mut_ptr(), 2),
+            2
+        );
+        assert_eq!(out_x, [0.0, 1.0]);
+        assert_eq!(out_y, [0.0, 1.0]);
+        // Ids count up from zero in the order bodies are added
+        assert_eq!(
+            space_clicker_add_bodies(
+                simulation,
+                x.as_ptr(),
+                y.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                1
+            ),
+            4
+        );
+        let mut ids: [u64; 4] = [0; 4];
+        assert_eq!(space_clicker_copy_ids(simulation, ids.as_mut_ptr(), 4), 4);
+        assert_eq!(ids, [0, 1, 2, 3]);
+        space_clicker_destroy(simulation);
+
+        // A NULL handle is ignored
+        let null = ptr::null_mut();
+        assert_eq!(space_clicker_num_bodies(null), 0);
+        assert_eq!(
+            space_clicker_add_bodies(
+                null,
+                x.as_ptr(),
+                y.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                3
+            ),
+            0
+        );
+        space_clicker_step(null, 1);
+        space_clicker_destroy(null);
+    }
+}
+
+#[test]
+fn removal_of_distant_and_unbound_bodies() {
+    unsafe {
+        // Three bodies near the origin and one far away
+        let simulation = space_clicker_create();
+        let x: [f64; 4] = [0.0, 1.0, 0.0, 100.0];
+        let y: [f64; 4] = [0.0, 0.0, 1.0, 0.0];
+        space_clicker_add_bodies(
+            simulation,
+            x.as_ptr(),
+            y.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            4,
+        );
+        space_clicker_set_dt(simulation, 0.001);
+        space_clicker_step(simulation, 1);
+        assert_eq!(space_clicker_num_bodies(simulation), 4);
+        space_clicker_set_removal(simulation, 50.0, false);
+        space_clicker_step(simulation, 1);
+        assert_eq!(space_clicker_num_bodies(simulation), 3);
+        let mut ids: [u64; 4] = [0; 4];
+        assert_eq!(space_clicker_copy_ids(simulation, ids.as_mut_ptr(), 4), 3);
+        assert_eq!(ids[..3], [0, 1, 2]);
+        space_clicker_destroy(simulation);
+
+        // The same bodies with the last one close by but fast enough to escape
+        let simulation = space_clicker_create();
+        let x: [f64; 4] = [0.0, 1.0, 0.0, 3.0];
+        let vx: [f64; 4] = [0.0, 0.0, 0.0, 4.0];
+        let vy: [f64; 4] = [0.0; 4];
+        space_clicker_add_bodies(
+            simulation,
+            x.as_ptr(),
+            y.as_ptr(),
+            vx.as_ptr(),
+            vy.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            4,
+        );
+        space_clicker_set_dt(simulation, 0.001);
+        space_clicker_set_removal(simulation, 0.0, true);
+        space_clicker_step(simulation, 1);
+        assert_eq!(space_clicker_num_bodies(simulation), 3);
+        assert_eq!(space_clicker_copy_ids(simulation, ids.as_mut_ptr(), 4), 3);
+        assert_eq!(ids[..3], [0, 1, 2]);
+        // Velocities stay with their bodies
+        let mut out_vx: [f64; 3] = [1.0; 3];
+        let mut out_vy: [f64; 3] = [1.0; 3];
+        space_clicker_copy_velocities(simulation, out_vx.as_mut_ptr(), out_vy.as_mut_ptr(), 3);
+        assert!(out_vx.iter().all(|vx| vx.abs() < 0.1));
+        space_clicker_destroy(simulation);
+    }
+}
+
+#[test]
+fn masses_and_pinned_bodies() {
+    unsafe {
+        // A heavy pinned body at the origin, a free body falling towards it and
+        // a light pinned body far away
+        let simulation = space_clicker_create();
+        let x: [f64; 3] = [0.0, 10.0, 100.0];
+        let y: [f64; 3] = [0.0; 3];
+        let masses: [f64; 3] = [1000.0, 1.0, 1.0];
+        let fixed: [bool; 3] = [true, false, true];
+        space_clicker_add_bodies(
+            simulation,
+            x.as_ptr(),
+            y.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            masses.as_ptr(),
+            fixed.as_ptr(),
+            3,
+        );
+        space_clicker_set_dt(simulation, 0.01);
+        space_clicker_step(simulation, 100);
+        let mut out_x: [f64; 3] = [0.0; 3];
+        let mut out_y: [f64; 3] = [0.0; 3];
+        space_clicker_copy_positions(simulation, out_x.as_mut_ptr(), out_y.as_mut_ptr(), 3);
+        assert_eq!(out_x[0], 0.0);
+        assert_eq!(out_x[2], 100.0);
+        // The heavy body pulls the free one about 5 units in a unit of time
+        assert!(out_x[1] < 7.0, "{}", out_x[1]);
+
+        // Pinned bodies are kept unless the policy says otherwise
+        space_clicker_set_removal(simulation, 50.0, false);
+        space_clicker_step(simulation, 1);
+        assert_eq!(space_clicker_num_bodies(simulation), 3);
+        space_clicker_set_remove_pinned(simulation, true);
+        space_clicker_step(simulation, 1);
+        assert_eq!(space_clicker_num_bodies(simulation), 2);
+        let mut ids: [u64; 3] = [0; 3];
+        assert_eq!(space_clicker_copy_ids(simulation, ids.as_mut_ptr(), 3), 2);
+        assert_eq!(ids[..2], [0, 1]);
+        space_clicker_destroy(simulation);
+    }
+}
+
+// Compiles tests/c/ffi.c against include/space_clicker.h, links it with the
+// static library and runs it
+#[cfg(unix)]
+#[test]
+fn c_program_uses_the_header() {
+    let crate_dir: &Path = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Integration tests run from target/<profile>/deps, beside the library
+    let profile_dir: PathBuf = env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(Path::parent)
+        .unwrap()
+        .to_path_buf();
+    let program: PathBuf =
+        env::temp_dir().join(format!("space_clicker_ffi_{}", std::process::id()));
+    let compiler: String = env::var("CC").unwrap_or_else(|_| String::from("cc"));
+    let output: Output = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(crate_dir.join("include"))
+        .arg(crate_dir.join("tests/c/ffi.c"))
+        .arg(profile_dir.join("libspace_clicker.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output: Output = Command::new(&program).output().unwrap();
+    std::fs::remove_file(&program).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
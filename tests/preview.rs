@@ -0,0 +1,58 @@
+// Predicts the path of spawned bodies through the field of the others
+use space_clicker::simulation::preview::SpawnPreview;
+use space_clicker::simulation::Simulation;
+
+#[test]
+fn path_is_straight_without_other_bodies() {
+    let mut preview: SpawnPreview = SpawnPreview::new();
+    preview.steps = 50;
+    preview.predict(None, (1.0, 2.0), (0.5, -0.25), 1.0, 0.1);
+    let path: &[(f64, f64)] = preview.path();
+    assert_eq!(path.len(), 51);
+    for (step, &(x, y)) in path.iter().enumerate() {
+        let t: f64 = step as f64 * 0.1;
+        assert!((x - (1.0 + 0.5 * t)).abs() < 1e-12, "step {}", step);
+        assert!((y - (2.0 - 0.25 * t)).abs() < 1e-12, "step {}", step);
+    }
+    // No steps turns the preview off
+    preview.steps = 0;
+    preview.predict(None, (1.0, 2.0), (0.5, -0.25), 1.0, 0.1);
+    assert!(preview.path().is_empty());
+}
+
+#[test]
+fn path_bends_towards_a_massive_body() {
+    // A heavy body at the origin, and a light one that gives the tree some width
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.add_massive_body(0.0, 0.0, 0.0, 0.0, 100.0, true);
+    simulation.add_massive_body(20.0, 20.0, 0.0, 0.0, 0.001, true);
+    simulation.fit_tree_root();
+    // Pinned bodies stay put, leaving the tree built for them
+    simulation.step();
+
+    let mut preview: SpawnPreview = SpawnPreview::new();
+    preview.steps = 100;
+    // Passing above the heavy body, moving right
+    preview.predict(
+        Some(&simulation.bh_tree),
+        (-5.0, -10.0),
+        (2.0, 0.0),
+        1.0,
+        0.01,
+    );
+    let path: &[(f64, f64)] = preview.path();
+    assert_eq!(path.len(), 101);
+    assert_eq!(path[0], (-5.0, -10.0));
+    // Every step is pulled further down towards the body, and less so without gravity
+    assert!(path.windows(2).all(|pair| pair[1].1 > pair[0].1));
+    let (_, end_y): (f64, f64) = path[100];
+    preview.predict(
+        Some(&simulation.bh_tree),
+        (-5.0, -10.0),
+        (2.0, 0.0),
+        0.0,
+        0.01,
+    );
+    assert!((preview.path()[100].1 + 10.0).abs() < 1e-12);
+    assert!(end_y > -10.0 + 0.1, "{}", end_y);
+}
@@ -0,0 +1,165 @@
+// Picks bodies to remove and removes them from the simulation core's arrays
+use space_clicker::simulation::removal::{
+    find_removals, remove_indexes, RemovalPolicy, RemovalReason,
+};
+use space_clicker::simulation::Simulation;
+
+// Three unit masses at rest near the origin, then the bodies under test
+struct Bodies {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    vx: Vec<f64>,
+    vy: Vec<f64>,
+    masses: Vec<f64>,
+    fixed: Vec<bool>,
+}
+impl Bodies {
+    fn new() -> Bodies {
+        Bodies {
+            x: vec![-1.0, 1.0, 0.0],
+            y: vec![0.0, 0.0, 1.0],
+            vx: vec![0.0; 3],
+            vy: vec![0.0; 3],
+            masses: vec![1.0; 3],
+            fixed: vec![false; 3],
+        }
+    }
+    fn with(mut self, position: (f64, f64), velocity: (f64, f64), fixed: bool) -> Bodies {
+        self.x.push(position.0);
+        self.y.push(position.1);
+        self.vx.push(velocity.0);
+        self.vy.push(velocity.1);
+        self.masses.push(0.001);
+        self.fixed.push(fixed);
+        self
+    }
+    fn removals(&self, policy: RemovalPolicy) -> Vec<(usize, RemovalReason)> {
+        find_removals(
+            &self.x,
+            &self.y,
+            &self.vx,
+            &self.vy,
+            &self.masses,
+            &self.fixed,
+            1.0,
+            &policy,
+        )
+    }
+}
+
+const DISTANCE: RemovalPolicy = RemovalPolicy {
+    max_distance: Some(50.0),
+    remove_unbound: false,
+    remove_pinned: false,
+};
+const UNBOUND: RemovalPolicy = RemovalPolicy {
+    max_distance: None,
+    remove_unbound: true,
+    remove_pinned: false,
+};
+
+#[test]
+fn distant_bodies_are_removed() {
+    let bodies: Bodies = Bodies::new()
+        .with((100.0, 0.0), (0.0, 0.0), false)
+        .with((0.0, 40.0), (0.0, 0.0), false)
+        .with((0.0, -100.0), (0.0, 0.0), false);
+    assert_eq!(
+        bodies.removals(DISTANCE),
+        [(3, RemovalReason::Distance), (5, RemovalReason::Distance)]
+    );
+    // Nothing is removed without a policy
+    assert_eq!(bodies.removals(RemovalPolicy::default()), []);
+}
+
+#[test]
+fn only_receding_unbound_bodies_are_removed() {
+    let bodies: Bodies = Bodies::new()
+        // Fast and moving away
+        .with((5.0, 0.0), (10.0, 0.0), false)
+        // Fast but moving towards the centre
+        .with((-5.0, 0.0), (10.0, 0.0), false)
+        // Moving away too slowly to escape
+        .with((0.0, 5.0), (0.0, 0.1), false);
+    assert_eq!(bodies.removals(UNBOUND), [(3, RemovalReason::Unbound)]);
+    // Distance is checked first
+    let policy: RemovalPolicy = RemovalPolicy {
+        max_distance: Some(4.0),
+        remove_unbound: true,
+        remove_pinned: false,
+    };
+    let reasons: Vec<RemovalReason> = bodies
+        .removals(policy)
+        .into_iter()
+        .map(|(_, reason)| reason)
+        .collect();
+    assert_eq!(reasons, [RemovalReason::Distance; 3]);
+}
+
+#[test]
+fn pinned_bodies_are_only_removed_if_the_policy_says_so() {
+    let bodies: Bodies =
+        Bodies::new()
+            .with((100.0, 0.0), (0.0, 0.0), true)
+            .with((5.0, 0.0), (10.0, 0.0), true);
+    let mut policy: RemovalPolicy = RemovalPolicy {
+        max_distance: Some(50.0),
+        remove_unbound: true,
+        remove_pinned: false,
+    };
+    assert_eq!(bodies.removals(policy), []);
+    policy.remove_pinned = true;
+    assert_eq!(
+        bodies.removals(policy),
+        [(3, RemovalReason::Distance), (4, RemovalReason::Unbound)]
+    );
+}
+
+#[test]
+fn remove_indexes_keeps_the_rest_in_order() {
+    let mut values: Vec<char> = vec!['a', 'b', 'c', 'd', 'e', 'f'];
+    remove_indexes(
+        &mut values,
+        &[
+            (0, RemovalReason::Erased),
+            (3, RemovalReason::Distance),
+            (5, RemovalReason::Unbound),
+        ],
+    );
+    assert_eq!(values, ['b', 'c', 'e']);
+    remove_indexes(&mut values, &[]);
+    assert_eq!(values, ['b', 'c', 'e']);
+}
+
+#[test]
+fn removed_bodies_leave_every_array_aligned() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    // Each body's mass is its index plus one, so its values can be told apart
+    for (i, &x) in [0.0, 200.0, 1.0, -300.0, -1.0].iter().enumerate() {
+        let id: u64 =
+            simulation.add_massive_body(x, i as f64, 0.5 * i as f64, 0.0, i as f64 + 1.0, false);
+        assert_eq!(id, i as u64);
+    }
+    simulation.removal.max_distance = Some(100.0);
+    let removed: Vec<(u64, RemovalReason)> = simulation.remove_bodies();
+    assert_eq!(
+        removed,
+        [(1, RemovalReason::Distance), (3, RemovalReason::Distance)]
+    );
+    assert_eq!(simulation.num_bodies, 3);
+    assert_eq!(simulation.ids, [0, 2, 4]);
+    assert_eq!(simulation.x, [0.0, 1.0, -1.0]);
+    assert_eq!(simulation.y, [0.0, 2.0, 4.0]);
+    assert_eq!(simulation.vx, [0.0, 1.0, 2.0]);
+    assert_eq!(simulation.masses, [1.0, 3.0, 5.0]);
+    for length in [
+        simulation.vy.len(),
+        simulation.ax.len(),
+        simulation.ay.len(),
+        simulation.fixed.len(),
+        simulation.batches.len(),
+        simulation.com_distances.len(),
+    ] {
+        assert_eq!(length, 3);
+    }
+}
@@ -0,0 +1,64 @@
+// Runs the simulation core forwards and then backwards to where it started
+use space_clicker::simulation::Simulation;
+
+const NUM_STEPS: usize = 200;
+
+#[test]
+fn reversed_steps_retrace_the_forward_steps() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.add_body(-1.0, 0.0, 0.0, -0.4);
+    simulation.add_body(1.0, 0.0, 0.0, 0.4);
+    simulation.add_massive_body(0.0, 2.0, 0.3, 0.0, 0.5, false);
+    simulation.add_massive_body(3.0, -2.0, -0.2, 0.1, 2.0, false);
+    // A pinned body with a velocity that must never move it
+    simulation.add_massive_body(-3.0, -1.0, 1.0, 1.0, 5.0, true);
+    simulation.dt = 0.01;
+    let start: [Vec<f64>; 4] = [
+        simulation.x.clone(),
+        simulation.y.clone(),
+        simulation.vx.clone(),
+        simulation.vy.clone(),
+    ];
+
+    for _ in 0..NUM_STEPS {
+        simulation.fit_tree_root();
+        simulation.step();
+    }
+    // The bodies have moved, apart from the pinned one
+    assert!((simulation.y[0] - start[1][0]).abs() > 0.1);
+    assert_eq!(
+        (
+            simulation.x[4],
+            simulation.y[4],
+            simulation.vx[4],
+            simulation.vy[4]
+        ),
+        (-3.0, -1.0, 1.0, 1.0)
+    );
+
+    simulation.reversed = true;
+    for _ in 0..NUM_STEPS {
+        simulation.step();
+    }
+    let end: [&Vec<f64>; 4] = [&simulation.x, &simulation.y, &simulation.vx, &simulation.vy];
+    for (start_values, end_values) in start.iter().zip(end) {
+        for (start_value, end_value) in start_values.iter().zip(end_values) {
+            assert!(
+                (start_value - end_value).abs() < 1e-9,
+                "{} came back as {}",
+                start_value,
+                end_value
+            );
+        }
+    }
+    // The pinned body was skipped both ways
+    assert_eq!(
+        (
+            simulation.x[4],
+            simulation.y[4],
+            simulation.vx[4],
+            simulation.vy[4]
+        ),
+        (-3.0, -1.0, 1.0, 1.0)
+    );
+}
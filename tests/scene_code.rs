@@ -0,0 +1,216 @@
+// Shares scenes as scene codes and reads them back, including damaged codes
+use std::io::Write;
+
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use space_clicker::simulation::Simulation;
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// A scene of bodies with distinct x, so they keep their order by x through a code
+fn scene(num_bodies: usize) -> Simulation {
+    let mut simulation: Simulation = Simulation::new_empty();
+    for i in 0..num_bodies {
+        let t: f64 = i as f64;
+        let mass: f64 = if i % 3 == 0 { 2.5 } else { 1.0 };
+        simulation.add_massive_body(
+            (t * 7.3) % 50.0 + t * 0.01,
+            (t * 1.7).sin() * 20.0,
+            (t * 0.9).cos(),
+            -t * 0.05,
+            mass,
+            i % 5 == 0,
+        );
+    }
+    simulation.dt = 0.02;
+    simulation.gravity = 3.0;
+    simulation
+}
+
+// The body indexes in order of increasing x
+fn order_by_x(simulation: &Simulation) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..simulation.num_bodies).collect();
+    order.sort_by(|&a, &b| simulation.x[a].total_cmp(&simulation.x[b]));
+    order
+}
+
+// A scene code from the raw bytes the exporter would deflate
+fn code_from_bytes(data: &[u8]) -> String {
+    let mut encoder: DeflateEncoder<Vec<u8>> = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).unwrap();
+    let compressed: Vec<u8> = encoder.finish().unwrap();
+    let mut code: String = String::new();
+    for chunk in compressed.chunks(3) {
+        let bits: u32 = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            code.push(BASE64URL[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    code
+}
+
+// The start of a version 3 code for num_bodies bodies, up to the quantized columns
+fn code_header(num_bodies: u32) -> Vec<u8> {
+    let mut data: Vec<u8> = b"SCN\x03".to_vec();
+    for value in [0.01, 1.0, 10.0, 1.0, 1.0] {
+        data.extend_from_slice(&f64::to_le_bytes(value));
+    }
+    data.extend_from_slice(&num_bodies.to_le_bytes());
+    for _ in 0..4 {
+        data.extend_from_slice(&f64::to_le_bytes(0.0));
+        data.extend_from_slice(&f64::to_le_bytes(1.0));
+    }
+    data
+}
+
+#[test]
+fn roundtrip_within_one_quantization_step() {
+    let saved: Simulation = scene(40);
+    let code: String = saved.export_scene_code().unwrap();
+    let mut loaded: Simulation = Simulation::new_empty();
+    loaded.import_scene_code(&code).unwrap();
+    assert_eq!(loaded.num_bodies, saved.num_bodies);
+    assert_eq!(loaded.dt, saved.dt);
+    assert_eq!(loaded.gravity, saved.gravity);
+    let columns = |simulation: &Simulation| {
+        [
+            simulation.x.clone(),
+            simulation.y.clone(),
+            simulation.vx.clone(),
+            simulation.vy.clone(),
+        ]
+    };
+    // The loaded bodies are in order of x
+    let order: Vec<usize> = order_by_x(&saved);
+    for (saved_values, loaded_values) in columns(&saved).iter().zip(columns(&loaded)) {
+        let min: f64 = saved_values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max: f64 = saved_values
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let step: f64 = (max - min) / u16::MAX as f64;
+        for (position, &i) in order.iter().enumerate() {
+            let error: f64 = (loaded_values[position] - saved_values[i]).abs();
+            assert!(error <= step, "error {} over a step of {}", error, step);
+        }
+    }
+    for (position, &i) in order.iter().enumerate() {
+        assert_eq!(loaded.masses[position], saved.masses[i]);
+        assert_eq!(loaded.fixed[position], saved.fixed[i]);
+    }
+}
+
+#[test]
+fn codes_of_every_length_decode() {
+    // Codes end in partial base64url chunks of two and three characters as well as whole ones
+    let mut endings: Vec<usize> = Vec::new();
+    for num_bodies in 0..12 {
+        let saved: Simulation = scene(num_bodies);
+        let code: String = saved.export_scene_code().unwrap();
+        endings.push(code.len() % 4);
+        let mut loaded: Simulation = Simulation::new_empty();
+        loaded.import_scene_code(&code).unwrap();
+        assert_eq!(loaded.num_bodies, num_bodies);
+        let order: Vec<usize> = order_by_x(&saved);
+        for (position, &i) in order.iter().enumerate() {
+            assert_eq!(loaded.masses[position], saved.masses[i]);
+        }
+    }
+    for ending in [0, 2, 3] {
+        assert!(
+            endings.contains(&ending),
+            "no code ends in {} characters",
+            ending
+        );
+    }
+}
+
+#[test]
+fn truncated_codes_are_rejected() {
+    let code: String = scene(20).export_scene_code().unwrap();
+    let mut simulation: Simulation = scene(2);
+    for len in 0..code.len() {
+        assert!(
+            simulation.import_scene_code(&code[..len]).is_err(),
+            "{} characters",
+            len
+        );
+        assert_eq!(simulation.num_bodies, 2);
+    }
+}
+
+#[test]
+fn corrupt_codes_are_rejected_without_panicking() {
+    let mut simulation: Simulation = scene(2);
+    // Characters outside base64url
+    assert!(simulation.import_scene_code("abc+def").is_err());
+    assert!(simulation.import_scene_code("abc=").is_err());
+    // Every single character changed, which may still happen to decode
+    let code: String = scene(20).export_scene_code().unwrap();
+    for i in 0..code.len() {
+        let mut changed: Vec<u8> = code.clone().into_bytes();
+        changed[i] = if changed[i] == b'A' { b'_' } else { b'A' };
+        let _ = simulation.import_scene_code(std::str::from_utf8(&changed).unwrap());
+    }
+    let mut simulation: Simulation = scene(2);
+    // Not a scene code, or from a newer version
+    assert!(simulation
+        .import_scene_code(&code_from_bytes(b"PNG\x01"))
+        .is_err());
+    assert!(simulation
+        .import_scene_code(&code_from_bytes(b"SCN\xff"))
+        .is_err());
+    // More bodies than the code holds
+    let mut data: Vec<u8> = code_header(u32::MAX);
+    data.extend_from_slice(&[0; 64]);
+    assert!(simulation
+        .import_scene_code(&code_from_bytes(&data))
+        .is_err());
+    // x deltas past the 16 bit range
+    let mut data: Vec<u8> = code_header(2);
+    data.extend_from_slice(&[0xff, 0xff, 0x03, 0xff, 0xff, 0x03]);
+    data.extend_from_slice(&[0; 12]);
+    assert!(simulation
+        .import_scene_code(&code_from_bytes(&data))
+        .is_err());
+    // A varint longer than three bytes
+    let mut data: Vec<u8> = code_header(1);
+    data.extend_from_slice(&[0x80, 0x80, 0x80, 0x00]);
+    data.extend_from_slice(&[0; 6]);
+    assert!(simulation
+        .import_scene_code(&code_from_bytes(&data))
+        .is_err());
+    // A mass for a body past the last
+    let mut data: Vec<u8> = code_header(1);
+    data.extend_from_slice(&[0; 7]);
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&f64::to_le_bytes(2.0));
+    data.push(0);
+    assert!(simulation
+        .import_scene_code(&code_from_bytes(&data))
+        .is_err());
+    // A spawn shape name that isn't UTF-8, and one that isn't a shape
+    let with_shape = |name: &[u8]| -> String {
+        let mut data: Vec<u8> = code_header(1);
+        data.extend_from_slice(&[0; 7]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.push(name.len() as u8);
+        data.extend_from_slice(name);
+        data.extend_from_slice(&f64::to_le_bytes(0.0));
+        code_from_bytes(&data)
+    };
+    assert!(simulation
+        .import_scene_code(&with_shape(b"\xff\xfe"))
+        .is_err());
+    assert!(simulation
+        .import_scene_code(&with_shape(b"hexagon"))
+        .is_err());
+    assert_eq!(simulation.num_bodies, 2);
+    // The same code with a real shape is read
+    simulation.import_scene_code(&with_shape(b"disk")).unwrap();
+    assert_eq!(simulation.num_bodies, 1);
+}
@@ -0,0 +1,111 @@
+// Loads saved scenes into the simulation core, which checks them before replacing the scene
+use space_clicker::simulation::spawner::SpawnShape;
+use space_clicker::simulation::state::SceneState;
+use space_clicker::simulation::Simulation;
+
+// A scene of three bodies with the given ids, saved by another simulation
+fn scene_with_ids(ids: [u64; 3]) -> String {
+    let mut simulation: Simulation = Simulation::new_empty();
+    for i in 0..3 {
+        simulation.add_body(i as f64, 0.0, 0.0, 0.0);
+    }
+    let json: String = simulation.export_state().unwrap();
+    json.replace(
+        r#""ids":[0,1,2]"#,
+        &format!(r#""ids":{:?}"#, ids).replace(' ', ""),
+    )
+}
+
+// Loads a saved scene of three bodies after the given change, which must make
+// it invalid, and returns the error
+fn rejection(change: impl FnOnce(&mut SceneState)) -> String {
+    let mut state: SceneState = serde_json::from_str(&scene_with_ids([0, 1, 2])).unwrap();
+    change(&mut state);
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.dt = 0.5;
+    let err: String = simulation.load_state(state).unwrap_err();
+    // The scene is unchanged
+    assert_eq!(simulation.dt, 0.5);
+    assert_eq!(simulation.num_bodies, 0);
+    err
+}
+
+#[test]
+fn duplicate_ids_are_rejected() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation.add_body(5.0, 5.0, 0.0, 0.0);
+    let err: String = simulation
+        .import_state(&scene_with_ids([4, 9, 4]))
+        .unwrap_err();
+    assert!(err.contains("id 4"), "{}", err);
+    // The scene is unchanged
+    assert_eq!(simulation.num_bodies, 1);
+    assert_eq!(simulation.ids, [0]);
+}
+
+#[test]
+fn largest_id_is_rejected() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    let err: String = simulation
+        .import_state(&scene_with_ids([1, u64::MAX, 2]))
+        .unwrap_err();
+    assert!(err.contains("too large"), "{}", err);
+    assert_eq!(simulation.num_bodies, 0);
+}
+
+#[test]
+fn loaded_ids_are_kept_and_new_bodies_follow_them() {
+    let mut simulation: Simulation = Simulation::new_empty();
+    simulation
+        .import_state(&scene_with_ids([7, 3, 11]))
+        .unwrap();
+    assert_eq!(simulation.ids, [7, 3, 11]);
+    assert_eq!(simulation.add_body(0.0, 1.0, 0.0, 0.0), 12);
+}
+
+#[test]
+fn spawn_shape_and_spin_are_kept() {
+    let mut saved: Simulation = Simulation::new_empty();
+    saved.spawner.shape = SpawnShape::Ring;
+    saved.spawner.spin = -1.0;
+    let mut from_json: Simulation = Simulation::new_empty();
+    from_json
+        .import_state(&saved.export_state().unwrap())
+        .unwrap();
+    let mut from_code: Simulation = Simulation::new_empty();
+    from_code
+        .import_scene_code(&saved.export_scene_code().unwrap())
+        .unwrap();
+    for simulation in [from_json, from_code] {
+        assert!(simulation.spawner.shape == SpawnShape::Ring);
+        assert_eq!(simulation.spawner.spin, -1.0);
+    }
+}
+
+#[test]
+fn fields_with_the_wrong_length_are_named() {
+    assert!(rejection(|state| state.bodies.y.truncate(2)).contains("'y'"));
+    assert!(rejection(|state| state.bodies.vx.truncate(2)).contains("'vx'"));
+    assert!(rejection(|state| state.bodies.vy.truncate(2)).contains("'vy'"));
+    let err: String = rejection(|state| state.bodies.masses = Some(vec![1.0; 2]));
+    assert_eq!(err, "The scene has 2 values for 'masses' but 3 bodies");
+    assert!(rejection(|state| state.bodies.fixed = Some(vec![false; 4])).contains("'fixed'"));
+    assert!(rejection(|state| state.bodies.batches = Some(Vec::new())).contains("'batches'"));
+    assert!(rejection(|state| state.bodies.ids = Some(vec![0, 1])).contains("'ids'"));
+}
+
+#[test]
+fn non_finite_parameters_are_rejected() {
+    let err: String = rejection(|state| state.parameters.dt = f64::NAN);
+    assert_eq!(err, "The time step must be finite");
+    let err: String = rejection(|state| state.parameters.gravity = f64::INFINITY);
+    assert_eq!(err, "The gravity must be finite");
+    let err: String = rejection(|state| state.parameters.spawn_radius = f64::NAN);
+    assert_eq!(err, "The spawn radius must be finite");
+    let err: String = rejection(|state| state.parameters.spawn_speed = f64::NEG_INFINITY);
+    assert_eq!(err, "The spawn speed must be finite");
+    let err: String = rejection(|state| state.parameters.scale_multiplier = f64::NAN);
+    assert_eq!(err, "The scale multiplier must be finite");
+    let err: String = rejection(|state| state.parameters.spawn_spin = Some(f64::INFINITY));
+    assert_eq!(err, "The spawn spin must be finite");
+}
@@ -0,0 +1,81 @@
+// Samples spawn shapes and spins without drawing anything
+use rand::rngs::ThreadRng;
+
+use space_clicker::simulation::spawner::{spin_velocities, SpawnShape};
+
+const SIZE: f64 = 8.0;
+const NUM_SAMPLES: usize = 10_000;
+
+fn samples(shape: SpawnShape) -> Vec<(f64, f64)> {
+    let mut rng: ThreadRng = rand::thread_rng();
+    (0..NUM_SAMPLES)
+        .map(|_| shape.sample(SIZE, &mut rng))
+        .collect()
+}
+
+#[test]
+fn samples_stay_within_each_shape() {
+    let radius: f64 = SIZE / 2.0;
+    for (x, y) in samples(SpawnShape::Square) {
+        assert!(x.abs() <= radius && y.abs() <= radius, "({}, {})", x, y);
+    }
+    for (x, y) in samples(SpawnShape::Disk) {
+        assert!(x.hypot(y) <= radius, "({}, {})", x, y);
+    }
+    // Rings are a tenth of their radius wide
+    for (x, y) in samples(SpawnShape::Ring) {
+        let r: f64 = x.hypot(y);
+        assert!(r <= radius && r >= 0.9 * radius - 1e-12, "({}, {})", x, y);
+    }
+    for (x, y) in samples(SpawnShape::Line) {
+        assert!(x.abs() <= radius && y == 0.0, "({}, {})", x, y);
+    }
+    // Plummer blobs leave out the outer 1% of their mass, which lies beyond
+    // sqrt(99) scale radii of a quarter of the size
+    let plummer: Vec<f64> = samples(SpawnShape::Plummer)
+        .iter()
+        .map(|(x, y)| x.hypot(*y))
+        .collect();
+    let scale_radius: f64 = SIZE / 4.0;
+    assert!(plummer
+        .iter()
+        .all(|&r| r <= scale_radius * 99f64.sqrt() + 1e-9));
+    // Half the projected mass is within one scale radius
+    let inside: usize = plummer.iter().filter(|&&r| r <= scale_radius).count();
+    let fraction: f64 = inside as f64 / NUM_SAMPLES as f64;
+    assert!((fraction - 0.5 / 0.99).abs() < 0.05, "{}", fraction);
+}
+
+#[test]
+fn spin_velocities_have_no_net_momentum() {
+    let (x, y): (Vec<f64>, Vec<f64>) = samples(SpawnShape::Disk)
+        .into_iter()
+        .take(200)
+        .map(|(x, y)| (x + 30.0, y - 10.0))
+        .unzip();
+    for spin in [1.0, -0.5, 2.0] {
+        let velocities: Vec<(f64, f64)> = spin_velocities(&x, &y, spin, 1.0);
+        assert_eq!(velocities.len(), x.len());
+        let momentum: (f64, f64) = velocities
+            .iter()
+            .fold((0.0, 0.0), |total, v| (total.0 + v.0, total.1 + v.1));
+        assert!(
+            momentum.0.abs() < 1e-9 && momentum.1.abs() < 1e-9,
+            "{:?}",
+            momentum
+        );
+        assert!(velocities.iter().any(|v| v.0 != 0.0 || v.1 != 0.0));
+    }
+    // Reversing the spin reverses the velocities
+    let forwards: Vec<(f64, f64)> = spin_velocities(&x, &y, 1.0, 1.0);
+    let backwards: Vec<(f64, f64)> = spin_velocities(&x, &y, -1.0, 1.0);
+    for (forwards, backwards) in forwards.iter().zip(&backwards) {
+        assert!((forwards.0 + backwards.0).abs() < 1e-12);
+        assert!((forwards.1 + backwards.1).abs() < 1e-12);
+    }
+    // No spin, or a lone body, doesn't move
+    assert!(spin_velocities(&x, &y, 0.0, 1.0)
+        .iter()
+        .all(|&v| v == (0.0, 0.0)));
+    assert_eq!(spin_velocities(&[1.0], &[2.0], 1.0, 1.0), [(0.0, 0.0)]);
+}